@@ -5,6 +5,7 @@
 mod helpers;
 mod mpc_inner_product;
 mod mpc_prover;
+mod mpc_range_proof;
 mod transcript;
 
 use std::{borrow::Borrow, net::SocketAddr, process::exit};
@@ -6,10 +6,9 @@ use digest::Digest;
 use futures::future::join_all;
 use itertools::Itertools;
 use merlin::HashChainTranscript;
-use mpc_bulletproof::r1cs_mpc::MultiproverError;
+use mpc_bulletproof::r1cs_mpc::{MultiproverError, ProofStep};
 use mpc_bulletproof::{r1cs_mpc::SharedInnerProductProof, util, BulletproofGens, MpcTranscript};
 use mpc_bulletproof::{InnerProductProof, ProofError};
-use mpc_stark::error::MpcError;
 use mpc_stark::{
     algebra::{
         authenticated_scalar::AuthenticatedScalarResult,
@@ -409,7 +408,11 @@ fn test_malleable_proof(test_args: &IntegrationTestArgs) -> Result<(), String> {
     await_result(proof.open())
         .err()
         .map(|err| match err {
-            MultiproverError::Mpc(MpcError::AuthenticationError) => Ok(()),
+            // Each party attributes the failure to its peer
+            MultiproverError::InconsistentShares {
+                step: ProofStep::InnerProductScalars,
+                party,
+            } if party != test_args.party_id => Ok(()),
             _ => Err(err.to_string()),
         })
         .unwrap_or(Err("expected authentication error".to_string()))
@@ -2,13 +2,10 @@
 
 use futures::future::join_all;
 use mpc_bulletproof::{
-    r1cs::{
-        ConstraintSystem, R1CSError, RandomizableConstraintSystem, RandomizedConstraintSystem,
-        Variable, Verifier,
-    },
+    r1cs::{R1CSError, Verifier},
     r1cs_mpc::{
-        MpcConstraintSystem, MpcProver, MpcRandomizableConstraintSystem,
-        MpcRandomizedConstraintSystem, MpcVariable, MultiproverError, PartiallySharedR1CSProof,
+        GenericConstraintSystem, GenericRandomizableConstraintSystem, MpcProver, MultiproverError,
+        PartiallySharedR1CSProof, ProofStep,
     },
     BulletproofGens, PedersenGens,
 };
@@ -20,7 +17,6 @@ use mpc_stark::{
         authenticated_stark_point::AuthenticatedStarkPointOpenResult, scalar::Scalar,
         stark_curve::StarkPoint,
     },
-    error::MpcError,
     MpcFabric, PARTY0, PARTY1,
 };
 use rand::rngs::OsRng;
@@ -43,36 +39,17 @@ struct SimpleCircuit;
 
 impl SimpleCircuit {
     /// Gadget that applies constraints to the constraint system
-    fn gadget<CS: MpcRandomizableConstraintSystem>(
-        cs: &mut CS,
-        a: Vec<MpcVariable>,
-        b: Vec<MpcVariable>,
-        expected_out: MpcVariable,
-    ) -> Result<(), R1CSError> {
-        // Statement is (5 * a1 + 10 * a2) * (2 * b1 + 3 * b2) == 920
-        let (_, _, mul_out) = cs
-            .multiply(
-                &(Scalar::from(5u64) * &a[0] + Scalar::from(10u64) * &a[1]),
-                &(Scalar::from(2u64) * &b[0] + Scalar::from(3u64) * &b[1]),
-            )
-            .unwrap();
-
-        cs.constrain(mul_out - expected_out);
-
-        Ok(())
-    }
-
-    fn single_prover_gadget<CS: RandomizableConstraintSystem>(
+    fn gadget<CS: GenericConstraintSystem>(
         cs: &mut CS,
-        a: Vec<Variable>,
-        b: Vec<Variable>,
-        expected_out: Variable,
+        a: Vec<CS::Variable>,
+        b: Vec<CS::Variable>,
+        expected_out: CS::Variable,
     ) -> Result<(), R1CSError> {
         // Statement is (5 * a1 + 10 * a2) * (2 * b1 + 3 * b2) == 920
         let (_, _, mul_out) = cs.multiply(
-            Scalar::from(5u64) * a[0] + Scalar::from(10u64) * a[1],
-            Scalar::from(2u64) * b[0] + Scalar::from(3u64) * b[1],
-        );
+            a[0].clone() * Scalar::from(5u64) + a[1].clone() * Scalar::from(10u64),
+            b[0].clone() * Scalar::from(2u64) + b[1].clone() * Scalar::from(3u64),
+        )?;
 
         cs.constrain(mul_out - expected_out);
 
@@ -181,7 +158,7 @@ impl SimpleCircuit {
 
         let c_input = verifier.commit(opened_c_comm);
 
-        Self::single_prover_gadget(&mut verifier, a_input, b_input, c_input)
+        Self::gadget(&mut verifier, a_input, b_input, c_input)
             .map_err(MultiproverError::ProverError)?;
 
         verifier
@@ -301,7 +278,7 @@ fn test_r1cs_interleaved_witness(test_args: &IntegrationTestArgs) -> Result<(),
         .map(|val| verifier.commit(val))
         .map_err(|err| format!("Error opening `c` commitment: {:?}", err))?;
 
-    SimpleCircuit::single_prover_gadget(
+    SimpleCircuit::gadget(
         &mut verifier,
         vec![party0_vars[0], party1_vars[0]],
         vec![party1_vars[1], party0_vars[1]],
@@ -343,7 +320,11 @@ fn test_r1cs_proof_malleability(test_args: &IntegrationTestArgs) -> Result<(), S
     await_result(proof.open())
         .err()
         .map(|err| match err {
-            MultiproverError::Mpc(MpcError::AuthenticationError) => Ok(()),
+            // Each party attributes the failure to its peer
+            MultiproverError::InconsistentShares {
+                step: ProofStep::InnerProductScalars,
+                party,
+            } if party != test_args.party_id => Ok(()),
             _ => Err(err.to_string()),
         })
         .unwrap_or(Err(
@@ -355,75 +336,39 @@ fn test_r1cs_proof_malleability(test_args: &IntegrationTestArgs) -> Result<(), S
 pub struct ShuffleProof;
 
 impl ShuffleProof {
-    fn gadget<CS: MpcRandomizableConstraintSystem>(
-        cs: &mut CS,
-        x: Vec<MpcVariable>,
-        y: Vec<MpcVariable>,
-    ) -> Result<(), String> {
-        assert_eq!(x.len(), y.len());
-        let k = x.len();
-        if k == 1 {
-            cs.constrain(&y[0] - &x[0]);
-            return Ok(());
-        }
-
-        cs.specify_randomized_constraints(move |cs| {
-            let z = cs.challenge_scalar(b"shuffle challenge");
-
-            let (_, _, last_mulx_out) = cs.multiply(&(&x[k - 1] - &z), &(&x[k - 2] - &z)).unwrap();
-            let first_mulx_out = (0..k - 2).rev().fold(last_mulx_out, |acc, i| {
-                let (_, _, o) = cs.multiply(&acc.into(), &(&x[i] - &z)).unwrap();
-                o
-            });
-
-            let (_, _, last_muly_out) = cs.multiply(&(&y[k - 1] - &z), &(&y[k - 2] - &z)).unwrap();
-            let first_muly_out = (0..k - 2).rev().fold(last_muly_out, |acc, i| {
-                let (_, _, o) = cs.multiply(&acc.into(), &(&y[i] - &z)).unwrap();
-                o
-            });
-
-            cs.constrain(first_mulx_out - first_muly_out);
-
-            Ok(())
-        })
-        .map_err(|err| format!("Error building constraints: {:?}", err))?;
-
-        Ok(())
-    }
-
-    fn single_prover_gadget<CS: RandomizableConstraintSystem>(
+    fn gadget<CS: GenericRandomizableConstraintSystem>(
         cs: &mut CS,
-        x: Vec<Variable>,
-        y: Vec<Variable>,
+        x: Vec<CS::Variable>,
+        y: Vec<CS::Variable>,
     ) -> Result<(), R1CSError> {
         assert_eq!(x.len(), y.len());
         let k = x.len();
 
         if k == 1 {
-            cs.constrain(y[0] - x[0]);
+            cs.constrain(y[0].clone() - x[0].clone());
             return Ok(());
         }
 
         cs.specify_randomized_constraints(move |cs| {
             let z = cs.challenge_scalar(b"shuffle challenge");
-            let (_, _, last_mulx_out) = cs.multiply(x[k - 1] - z, x[k - 2] - z);
-            let first_mulx_out = (0..k - 2).rev().fold(last_mulx_out, |acc, i| {
-                let (_, _, o) = cs.multiply(acc.into(), x[i] - z);
-                o
-            });
-
-            let (_, _, last_muly_out) = cs.multiply(y[k - 1] - z, y[k - 2] - z);
-            let first_muly_out = (0..k - 2).rev().fold(last_muly_out, |acc, i| {
-                let (_, _, o) = cs.multiply(acc.into(), y[i] - z);
-                o
-            });
+            let (_, _, last_mulx_out) =
+                cs.multiply(x[k - 1].clone() - z.clone(), x[k - 2].clone() - z.clone())?;
+            let first_mulx_out = (0..k - 2).rev().try_fold(last_mulx_out, |acc, i| {
+                let (_, _, o) = cs.multiply(acc.into(), x[i].clone() - z.clone())?;
+                Ok::<_, R1CSError>(o)
+            })?;
+
+            let (_, _, last_muly_out) =
+                cs.multiply(y[k - 1].clone() - z.clone(), y[k - 2].clone() - z.clone())?;
+            let first_muly_out = (0..k - 2).rev().try_fold(last_muly_out, |acc, i| {
+                let (_, _, o) = cs.multiply(acc.into(), y[i].clone() - z.clone())?;
+                Ok::<_, R1CSError>(o)
+            })?;
 
             cs.constrain(first_mulx_out - first_muly_out);
 
             Ok(())
-        })?;
-
-        Ok(())
+        })
     }
 
     #[allow(clippy::type_complexity)]
@@ -508,8 +453,7 @@ impl ShuffleProof {
         let x_input = opened_x.iter().map(|x| verifier.commit(*x)).collect_vec();
         let y_input = opened_y.iter().map(|y| verifier.commit(*y)).collect_vec();
 
-        Self::single_prover_gadget(&mut verifier, x_input, y_input)
-            .map_err(MultiproverError::ProverError)?;
+        Self::gadget(&mut verifier, x_input, y_input).map_err(MultiproverError::ProverError)?;
 
         verifier
             .verify(&opened_proof, &bp_gens)
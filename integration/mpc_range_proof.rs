@@ -0,0 +1,142 @@
+//! Groups integration tests for collaborative range proofs over shared values
+
+use futures::future::join_all;
+use itertools::Itertools;
+use merlin::HashChainTranscript;
+use mpc_bulletproof::{
+    r1cs_mpc::{MultiproverError, SharedRangeProof},
+    BulletproofGens, MpcTranscript, PedersenGens, ProofError,
+};
+use mpc_stark::{
+    algebra::{
+        authenticated_scalar::AuthenticatedScalarResult, scalar::Scalar, stark_curve::StarkPoint,
+    },
+    error::MpcError,
+    network::PartyId,
+    MpcFabric, PARTY0, PARTY1,
+};
+use rand::rngs::OsRng;
+
+use crate::{helpers::await_result, IntegrationTest, IntegrationTestArgs};
+
+/// The seed of the test transcripts
+const TRANSCRIPT_SEED: &str = "test_range_proof";
+
+// ---------
+// | Utils |
+// ---------
+
+/// A value shared by its owner along with its blinder
+struct SharedWitness {
+    value: AuthenticatedScalarResult,
+    blinding: AuthenticatedScalarResult,
+}
+
+/// Share a `u64` value owned by `owning_party`
+fn share_witness(value: u64, owning_party: PartyId, fabric: &MpcFabric) -> SharedWitness {
+    let mut rng = OsRng {};
+
+    SharedWitness {
+        value: fabric.share_scalar(value, owning_party),
+        blinding: fabric.share_scalar(Scalar::random(&mut rng), owning_party),
+    }
+}
+
+/// Prove that each of the witnesses lies in `[0, 2^n)`, then open and verify the proof
+fn prove_and_verify(
+    witnesses: &[SharedWitness],
+    n: usize,
+    fabric: MpcFabric,
+) -> Result<(), MultiproverError> {
+    let m = witnesses.len();
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(n /* gens_capacity */, m /* party_capacity */);
+
+    let mut prover_transcript = MpcTranscript::new(
+        HashChainTranscript::new(TRANSCRIPT_SEED.as_bytes()),
+        fabric.clone(),
+    );
+    let (proof, value_commitments) = await_result(SharedRangeProof::prove_multiple(
+        &bp_gens,
+        &pc_gens,
+        &mut prover_transcript,
+        &witnesses.iter().map(|w| w.value.clone()).collect_vec(),
+        &witnesses.iter().map(|w| w.blinding.clone()).collect_vec(),
+        n,
+        &fabric,
+    ))?;
+
+    // Open the proof and the commitments
+    let opened_proof = await_result(proof.open())?;
+    let opened_commitments: Vec<StarkPoint> = await_result(join_all(value_commitments))
+        .into_iter()
+        .collect::<Result<Vec<_>, MpcError>>()
+        .map_err(MultiproverError::Mpc)?;
+
+    let mut verifier_transcript = HashChainTranscript::new(TRANSCRIPT_SEED.as_bytes());
+    opened_proof
+        .verify_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut verifier_transcript,
+            &opened_commitments,
+            n,
+        )
+        .map_err(MultiproverError::RangeProofError)
+}
+
+// ---------
+// | Tests |
+// ---------
+
+/// Tests a range proof over a single value held by one party
+fn test_single_range_proof(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    let fabric = test_args.mpc_fabric.clone();
+    let witness = share_witness(42 /* value */, PARTY0, &fabric);
+
+    prove_and_verify(&[witness], 32 /* n */, fabric)
+        .map_err(|err| format!("Verification error: {err:?}"))
+}
+
+/// Tests an aggregated range proof over values held by different parties
+fn test_aggregated_range_proof(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    let fabric = test_args.mpc_fabric.clone();
+    let witnesses = [
+        share_witness(u64::MAX, PARTY0, &fabric),
+        share_witness(0, PARTY1, &fabric),
+    ];
+
+    prove_and_verify(&witnesses, 64 /* n */, fabric)
+        .map_err(|err| format!("Verification error: {err:?}"))
+}
+
+/// Tests that a value outside of the range fails verification
+fn test_out_of_range_value(test_args: &IntegrationTestArgs) -> Result<(), String> {
+    // The value does not fit in 8 bits, so its 8 low-order bits do not add up to it
+    let fabric = test_args.mpc_fabric.clone();
+    let witness = share_witness(256 + 42 /* value */, PARTY1, &fabric);
+
+    match prove_and_verify(&[witness], 8 /* n */, fabric) {
+        Err(MultiproverError::RangeProofError(ProofError::VerificationError)) => Ok(()),
+        res => Err(format!("Expected verification error, got {res:?}")),
+    }
+}
+
+// ------------------
+// | Take Inventory |
+// ------------------
+
+inventory::submit!(IntegrationTest {
+    name: "mpc-range-proof::test_single_range_proof",
+    test_fn: test_single_range_proof,
+});
+
+inventory::submit!(IntegrationTest {
+    name: "mpc-range-proof::test_aggregated_range_proof",
+    test_fn: test_aggregated_range_proof,
+});
+
+inventory::submit!(IntegrationTest {
+    name: "mpc-range-proof::test_out_of_range_value",
+    test_fn: test_out_of_range_value,
+});
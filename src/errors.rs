@@ -3,7 +3,7 @@
 extern crate alloc;
 use alloc::vec::Vec;
 
-use mpc_stark::error::MpcError;
+use mpc_stark::{error::MpcError, network::PartyId, PARTY0, PARTY1};
 #[cfg(feature = "std")]
 use thiserror::Error;
 
@@ -78,14 +78,75 @@ pub enum MultiproverError {
     /// This error wraps a R1CS error that occurs during proof generation
     #[cfg_attr(feature = "multiprover", error("Error during proof generation"))]
     ProverError(R1CSError),
+    /// This error wraps a range proof error that occurs during proof generation
+    #[cfg_attr(feature = "multiprover", error("Error during range proof generation"))]
+    RangeProofError(ProofError),
     /// This error wraps the MPC library's MpcError
     #[cfg_attr(feature = "multiprover", error("Error during MPC communication"))]
     Mpc(MpcError),
+    /// This error occurs when the MAC check on a value opened during proof generation
+    /// fails, i.e. the counterparty sent a share inconsistent with its MAC share
+    ///
+    /// `party` is the party the local party attributes the failure to; in a two party
+    /// MPC an honest party can always attribute a failed MAC check to its peer
+    #[cfg_attr(
+        feature = "multiprover",
+        error("Party {party} sent inconsistent shares at {step:?}")
+    )]
+    InconsistentShares {
+        /// The step of the protocol at which the inconsistent shares were opened
+        step: ProofStep,
+        /// The party that sent the inconsistent shares
+        party: PartyId,
+    },
     /// TODO: Remove this placeholder error
     #[cfg_attr(feature = "multiprover", error("Functionality not yet implemented"))]
     NotImplemented,
 }
 
+impl MultiproverError {
+    /// Build the error for a failed opening of the values sent at `step`, as seen
+    /// by `local_party`
+    ///
+    /// Authentication failures are attributed to the counterparty, any other MPC
+    /// error is wrapped as is
+    pub(crate) fn from_opening(err: MpcError, step: ProofStep, local_party: PartyId) -> Self {
+        match err {
+            MpcError::AuthenticationError => MultiproverError::InconsistentShares {
+                step,
+                party: if local_party == PARTY0 {
+                    PARTY1
+                } else {
+                    PARTY0
+                },
+            },
+            err => MultiproverError::Mpc(err),
+        }
+    }
+}
+
+/// The steps of a collaborative proof at which shared values are opened
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProofStep {
+    /// The phase one commitments `A_I1`, `A_O1` and `S1`
+    PhaseOneCommitments,
+    /// The phase two commitments `A_I2`, `A_O2` and `S2`
+    PhaseTwoCommitments,
+    /// The masked values opened to decompose the values of a range proof into bits
+    BitDecomposition,
+    /// The range proof commitments `A` and `S` to the bits of the values and to
+    /// their blinding factors
+    BitCommitments,
+    /// The commitments `T_i` to the coefficients of \( t(x) \)
+    PolynomialCommitments,
+    /// The evaluation `t_x` and the blinding factors `t_x_blinding` and `e_blinding`
+    PolynomialEvaluation,
+    /// The `L` and `R` commitments sent in the given round of the inner product argument
+    InnerProductRound(usize),
+    /// The final scalars `a` and `b` of the inner product argument
+    InnerProductScalars,
+}
+
 /// Represents an error during the multiparty computation protocol for
 /// proof aggregation.
 ///
@@ -162,6 +223,24 @@ pub enum R1CSError {
     #[cfg_attr(feature = "std", error("R1CSProof did not verify correctly."))]
     VerificationError,
 
+    /// Occurs when one or more proofs in a batch fail to verify.
+    #[cfg_attr(
+        feature = "std",
+        error("R1CSProofs in batch did not verify correctly: {bad_proofs:?}")
+    )]
+    BatchVerificationError {
+        /// A vector with the indexes of the proofs that failed to verify.
+        bad_proofs: Vec<usize>,
+    },
+
+    /// Occurs when attempting to aggregate or batch verify proofs of an empty set
+    /// of constraint systems.
+    #[cfg_attr(
+        feature = "std",
+        error("Invalid aggregation size, must aggregate at least one constraint system.")
+    )]
+    InvalidAggregation,
+
     /// Occurs when trying to use a missing variable assignment.
     /// Used by gadgets that build the constraint system to signal that
     /// a variable assignment is not provided when the prover needs it.
@@ -174,6 +253,24 @@ pub enum R1CSError {
         /// The description of the reasons for the error.
         description: String,
     },
+
+    /// Occurs when a constraint system constructed with a circuit fingerprint
+    /// specifies a circuit that does not match it, or when a proof carries a
+    /// different fingerprint than the verifier's.
+    #[cfg_attr(
+        feature = "std",
+        error("The circuit does not match the fingerprint bound to the transcript.")
+    )]
+    CircuitFingerprintMismatch,
+
+    /// Wraps an error that occurred within a namespace of the constraint system.
+    #[cfg_attr(feature = "std", error("{error} (in namespace {namespace})"))]
+    InNamespace {
+        /// The namespace the error occurred in.
+        namespace: String,
+        /// The underlying error.
+        error: Box<R1CSError>,
+    },
 }
 
 #[cfg(feature = "multiprover")]
@@ -235,7 +235,6 @@ impl BulletproofGens {
     }
 
     /// Return an iterator over the aggregation of the parties' G generators with given size `n`.
-    #[cfg(test)]
     pub(crate) fn G(&self, n: usize, m: usize) -> impl Iterator<Item = &StarkPoint> {
         AggregatedGensIter {
             n,
@@ -247,7 +246,6 @@ impl BulletproofGens {
     }
 
     /// Return an iterator over the aggregation of the parties' H generators with given size `n`.
-    #[cfg(test)]
     pub(crate) fn H(&self, n: usize, m: usize) -> impl Iterator<Item = &StarkPoint> {
         AggregatedGensIter {
             n,
@@ -28,18 +28,24 @@ mod errors;
 mod generators;
 mod inner_product_proof;
 pub use inner_product_proof::*;
+#[cfg(feature = "std")]
+mod range_proof;
 pub mod transcript;
 
 pub use crate::errors::ProofError;
 pub use crate::generators::{BulletproofGens, BulletproofGensShare, PedersenGens};
+#[cfg(feature = "std")]
+pub use crate::range_proof::RangeProof;
 pub use transcript::{MpcTranscript, TranscriptProtocol};
-// pub use crate::range_proof::RangeProof;
 #[doc = include_str!("../docs/aggregation-api.md")]
 pub mod range_proof_mpc {
     pub use crate::errors::MPCError;
-    // pub use crate::range_proof::dealer;
-    // pub use crate::range_proof::messages;
-    // pub use crate::range_proof::party;
+    #[cfg(feature = "std")]
+    pub use crate::range_proof::dealer;
+    #[cfg(feature = "std")]
+    pub use crate::range_proof::messages;
+    #[cfg(feature = "std")]
+    pub use crate::range_proof::party;
 }
 
 #[cfg(feature = "std")]
@@ -47,3 +53,5 @@ pub mod range_proof_mpc {
 pub mod r1cs;
 #[cfg(feature = "multiprover")]
 pub mod r1cs_mpc;
+#[cfg(feature = "testing")]
+pub mod testing;
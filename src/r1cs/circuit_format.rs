@@ -0,0 +1,279 @@
+//! Definition of the serialized formats of a circuit's weights, used to ship circuits
+//! between services and to pin them as artifacts.
+
+use mpc_stark::algebra::scalar::{Scalar, SCALAR_BYTES};
+use serde::{Deserialize, Serialize};
+
+use super::{CircuitWeights, PhaseSizes, SparseReducedMatrix, SparseWeightRow};
+use crate::errors::R1CSError;
+use crate::util;
+
+/// The magic bytes that begin the binary encoding of a circuit
+pub const CIRCUIT_FORMAT_MAGIC: [u8; 4] = *b"R1CW";
+/// The version of the circuit formats produced by this crate
+pub const CIRCUIT_FORMAT_VERSION: u8 = 1;
+
+/// The number of bytes in the encoding of a dimension or an index
+const INDEX_BYTES: usize = 4;
+
+/// The dimensions of a circuit, which bound the indices referenced by its weights
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitDimensions {
+    /// The number of multiplication gates
+    pub n: usize,
+    /// The number of high-level variables committed to by the prover
+    pub m: usize,
+    /// The number of constraints
+    pub q: usize,
+}
+
+impl CircuitDimensions {
+    /// Check that the weights of a circuit have one row per constraint, and only
+    /// reference variables within the dimensions
+    pub fn check_weights(&self, weights: &CircuitWeights) -> Result<(), R1CSError> {
+        let rows_match = [&weights.w_l, &weights.w_r, &weights.w_o, &weights.w_v]
+            .iter()
+            .all(|matrix| matrix.0.len() == self.q);
+        let constants_in_range = weights.c.0.iter().all(|(i, _)| *i < self.q);
+
+        if !(rows_match && constants_in_range) {
+            return Err(R1CSError::GadgetError {
+                description: "circuit weights do not match the circuit's dimensions".to_string(),
+            });
+        }
+
+        PhaseSizes::from(*self).check_weights(weights)
+    }
+}
+
+impl From<CircuitDimensions> for PhaseSizes {
+    /// The serialized formats do not distinguish the phases of a circuit, so all
+    /// multipliers are counted in the first phase
+    fn from(dimensions: CircuitDimensions) -> Self {
+        PhaseSizes {
+            n1: dimensions.n,
+            n2: 0,
+            m: dimensions.m,
+        }
+    }
+}
+
+/// The JSON encoding of a circuit
+#[derive(Serialize, Deserialize)]
+struct JsonCircuit<W> {
+    /// The version of the format
+    version: u8,
+    /// The dimensions of the circuit
+    dimensions: CircuitDimensions,
+    /// The weights of the circuit
+    weights: W,
+}
+
+impl CircuitWeights {
+    /// The smallest dimensions that contain the weights
+    ///
+    /// Multipliers and commitments that no constraint references are not counted, so
+    /// prefer the dimensions of the constraint system the weights were exported from
+    pub fn dimensions(&self) -> CircuitDimensions {
+        let bound = |matrices: &[&SparseReducedMatrix]| {
+            matrices
+                .iter()
+                .flat_map(|matrix| matrix.0.iter())
+                .flat_map(|row| row.0.iter())
+                .map(|(i, _)| i + 1)
+                .max()
+                .unwrap_or(0)
+        };
+
+        CircuitDimensions {
+            n: bound(&[&self.w_l, &self.w_r, &self.w_o]),
+            m: bound(&[&self.w_v]),
+            q: self.num_constraints(),
+        }
+    }
+
+    /// Serialize the weights into the versioned binary format, declaring the given
+    /// dimensions
+    ///
+    /// The namespaces of the circuit are not encoded, they must be serialized separately
+    /// as a [`CircuitNamespaces`](super::CircuitNamespaces)
+    ///
+    /// # Layout
+    ///
+    /// All integers are little-endian `u32`s and all scalars are 32 bytes, big-endian:
+    /// * the magic bytes `R1CW` and a version byte,
+    /// * the dimensions `n`, `m` and `q`,
+    /// * for each of `W_L`, `W_R`, `W_O` and `W_V`, `q` rows, each a count of entries
+    ///   followed by that many pairs of a variable index and a weight,
+    /// * the constants `c`, as a count of entries followed by that many pairs of a
+    ///   constraint index and a constant.
+    pub fn to_bytes(&self, dimensions: &CircuitDimensions) -> Result<Vec<u8>, R1CSError> {
+        dimensions.check_weights(self)?;
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&CIRCUIT_FORMAT_MAGIC);
+        buf.push(CIRCUIT_FORMAT_VERSION);
+        for dimension in [dimensions.n, dimensions.m, dimensions.q] {
+            write_index(&mut buf, dimension)?;
+        }
+
+        for matrix in [&self.w_l, &self.w_r, &self.w_o, &self.w_v] {
+            for row in matrix.0.iter() {
+                write_row(&mut buf, row)?;
+            }
+        }
+        write_row(&mut buf, &self.c)?;
+
+        Ok(buf)
+    }
+
+    /// Deserialize weights from the versioned binary format, returning them along with
+    /// their declared dimensions
+    ///
+    /// Returns an error if the encoding is malformed, or if the weights do not match
+    /// the declared dimensions
+    pub fn from_bytes(slice: &[u8]) -> Result<(Self, CircuitDimensions), R1CSError> {
+        let mut reader = Reader { slice };
+        if reader.read::<4>()? != CIRCUIT_FORMAT_MAGIC {
+            return Err(R1CSError::FormatError);
+        }
+        if reader.read::<1>()?[0] != CIRCUIT_FORMAT_VERSION {
+            return Err(R1CSError::FormatError);
+        }
+
+        let dimensions = CircuitDimensions {
+            n: reader.read_index()?,
+            m: reader.read_index()?,
+            q: reader.read_index()?,
+        };
+
+        // Every row encodes at least its count, so reject dimensions the input is too
+        // short to hold before reading any rows
+        if reader.slice.len() / INDEX_BYTES < dimensions.q.saturating_mul(4).saturating_add(1) {
+            return Err(R1CSError::FormatError);
+        }
+
+        let mut read_matrix = || {
+            (0..dimensions.q)
+                .map(|_| reader.read_row())
+                .collect::<Result<Vec<_>, _>>()
+                .map(SparseReducedMatrix)
+        };
+        let w_l = read_matrix()?;
+        let w_r = read_matrix()?;
+        let w_o = read_matrix()?;
+        let w_v = read_matrix()?;
+        let c = reader.read_row()?;
+
+        if !reader.slice.is_empty() {
+            return Err(R1CSError::FormatError);
+        }
+
+        let weights = CircuitWeights {
+            w_l,
+            w_r,
+            w_o,
+            w_v,
+            c,
+        };
+        dimensions.check_weights(&weights)?;
+
+        Ok((weights, dimensions))
+    }
+
+    /// Serialize the weights and the given dimensions as versioned JSON
+    ///
+    /// The namespaces of the circuit are not encoded, they must be serialized separately
+    /// as a [`CircuitNamespaces`](super::CircuitNamespaces)
+    pub fn to_json(&self, dimensions: &CircuitDimensions) -> Result<String, R1CSError> {
+        dimensions.check_weights(self)?;
+
+        let circuit = JsonCircuit {
+            version: CIRCUIT_FORMAT_VERSION,
+            dimensions: *dimensions,
+            weights: self,
+        };
+        serde_json::to_string(&circuit).map_err(|_| R1CSError::FormatError)
+    }
+
+    /// Deserialize weights from versioned JSON, returning them along with their
+    /// declared dimensions
+    pub fn from_json(json: &str) -> Result<(Self, CircuitDimensions), R1CSError> {
+        let circuit: JsonCircuit<CircuitWeights> =
+            serde_json::from_str(json).map_err(|_| R1CSError::FormatError)?;
+        if circuit.version != CIRCUIT_FORMAT_VERSION {
+            return Err(R1CSError::FormatError);
+        }
+
+        circuit.dimensions.check_weights(&circuit.weights)?;
+        Ok((circuit.weights, circuit.dimensions))
+    }
+}
+
+/// Write a dimension or index, failing if it does not fit in the format
+fn write_index(buf: &mut Vec<u8>, index: usize) -> Result<(), R1CSError> {
+    let index = u32::try_from(index).map_err(|_| R1CSError::GadgetError {
+        description: "circuit dimensions exceed the limits of the format".to_string(),
+    })?;
+    buf.extend_from_slice(&index.to_le_bytes());
+    Ok(())
+}
+
+/// Write a sparse row as a count of entries followed by the entries
+fn write_row(buf: &mut Vec<u8>, row: &SparseWeightRow) -> Result<(), R1CSError> {
+    write_index(buf, row.0.len())?;
+    for (i, weight) in row.0.iter() {
+        write_index(buf, *i)?;
+        buf.extend_from_slice(&weight.to_bytes_be());
+    }
+
+    Ok(())
+}
+
+/// A cursor over the binary encoding of a circuit
+struct Reader<'a> {
+    /// The bytes remaining to be read
+    slice: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Read `N` bytes
+    fn read<const N: usize>(&mut self) -> Result<[u8; N], R1CSError> {
+        if self.slice.len() < N {
+            return Err(R1CSError::FormatError);
+        }
+
+        let bytes = util::read_exact::<N>(self.slice);
+        self.slice = &self.slice[N..];
+        Ok(bytes)
+    }
+
+    /// Read a dimension or index
+    fn read_index(&mut self) -> Result<usize, R1CSError> {
+        Ok(u32::from_le_bytes(self.read::<INDEX_BYTES>()?) as usize)
+    }
+
+    /// Read a scalar, rejecting non-canonical encodings
+    fn read_scalar(&mut self) -> Result<Scalar, R1CSError> {
+        let bytes = self.read::<SCALAR_BYTES>()?;
+        let scalar = Scalar::from_be_bytes_mod_order(&bytes);
+        if scalar.to_bytes_be()[..] != bytes[..] {
+            return Err(R1CSError::FormatError);
+        }
+
+        Ok(scalar)
+    }
+
+    /// Read a sparse row
+    fn read_row(&mut self) -> Result<SparseWeightRow, R1CSError> {
+        let count = self.read_index()?;
+        if self.slice.len() / (INDEX_BYTES + SCALAR_BYTES) < count {
+            return Err(R1CSError::FormatError);
+        }
+
+        (0..count)
+            .map(|_| Ok((self.read_index()?, self.read_scalar()?)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(SparseWeightRow)
+    }
+}
@@ -1,6 +1,11 @@
 //! Definition of the constraint system trait.
 
-use super::{LinearCombination, R1CSError, Variable};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::ops::{Deref, DerefMut};
+
+use super::{CircuitProfile, GadgetCost, LinearCombination, R1CSError, Variable};
+use itertools::Itertools;
 use merlin::HashChainTranscript as Transcript;
 use mpc_stark::algebra::scalar::Scalar;
 use serde::{Deserialize, Serialize};
@@ -40,6 +45,369 @@ pub struct CircuitWeights {
     pub c: SparseWeightRow,
 }
 
+impl CircuitWeights {
+    /// Extract the sparse-reduced weights of a list of constraints, given in the
+    /// order in which they were added to the constraint system
+    pub(crate) fn from_constraints(constraints: &[LinearCombination]) -> Self {
+        let (w_l, w_r, w_o, w_v, c) = constraints
+            // It's important that this iteration is in the correct order of the constraints,
+            // otherwise we'll write the wrong index for the given constant in a constraint
+            .iter()
+            .enumerate()
+            .map(|(i, lc)| {
+                let (w_l_row, w_r_row, w_o_row, w_v_row, c_i) = lc.extract_weights();
+                (w_l_row, w_r_row, w_o_row, w_v_row, (i, c_i))
+            })
+            .multiunzip();
+
+        CircuitWeights {
+            w_l,
+            w_r,
+            w_o,
+            w_v,
+            c,
+        }
+    }
+
+    /// The number of constraints represented by the weights
+    pub fn num_constraints(&self) -> usize {
+        [&self.w_l, &self.w_r, &self.w_o, &self.w_v]
+            .iter()
+            .map(|matrix| matrix.0.len())
+            .chain(self.c.0.iter().map(|(i, _)| i + 1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Rebuild the constraints represented by the weights, in the order in
+    /// which they were added to the constraint system
+    ///
+    /// This is the inverse of [`LinearCombination::extract_weights`], applied
+    /// to each constraint
+    pub(crate) fn to_constraints(&self) -> Vec<LinearCombination> {
+        let mut terms: Vec<Vec<(Variable, Scalar)>> = vec![Vec::new(); self.num_constraints()];
+
+        let matrices: [(&SparseReducedMatrix, fn(usize) -> Variable); 3] = [
+            (&self.w_l, Variable::MultiplierLeft),
+            (&self.w_r, Variable::MultiplierRight),
+            (&self.w_o, Variable::MultiplierOutput),
+        ];
+        for (matrix, var) in matrices {
+            for (i, row) in matrix.0.iter().enumerate() {
+                terms[i].extend(row.0.iter().map(|(j, coeff)| (var(*j), *coeff)));
+            }
+        }
+
+        // The committed weights and constants are negated when extracted, as they
+        // appear on the right hand side of the constraint
+        for (i, row) in self.w_v.0.iter().enumerate() {
+            terms[i].extend(
+                row.0
+                    .iter()
+                    .map(|(j, coeff)| (Variable::Committed(*j), -*coeff)),
+            );
+        }
+        for (i, c_i) in self.c.0.iter() {
+            terms[*i].push((Variable::One(), -*c_i));
+        }
+
+        terms
+            .into_iter()
+            .map(LinearCombination::from_iter)
+            .collect()
+    }
+}
+
+/// A constraint that is not satisfied by the assignments of a constraint system
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsatisfiedConstraint {
+    /// The index of the constraint, in the order in which constraints were added
+    pub index: usize,
+    /// The label of the constraint, if one was attached
+    pub label: Option<String>,
+}
+
+impl Display for UnsatisfiedConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "constraint {} ({}) is not satisfied", self.index, label),
+            None => write!(f, "constraint {} is not satisfied", self.index),
+        }
+    }
+}
+
+/// The namespaces recorded for the constraints and variables of a circuit
+///
+/// Each map is indexed in the same way as the corresponding `Variable`s and constraints;
+/// entries are only present for the constraints and variables created within a namespace,
+/// or for constraints added with a label.
+///
+/// The namespaces are kept apart from the [`CircuitWeights`], and are not part of their
+/// serialized formats.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitNamespaces {
+    /// The label of each labeled constraint
+    pub constraints: BTreeMap<usize, String>,
+    /// The namespace of each multiplication gate allocated within a namespace
+    pub multipliers: BTreeMap<usize, String>,
+    /// The namespace of each high-level variable committed within a namespace
+    pub commitments: BTreeMap<usize, String>,
+}
+
+impl CircuitNamespaces {
+    /// Get the name of a variable, prefixed by its namespace if it has one
+    pub fn variable_name(&self, var: &Variable) -> String {
+        let (namespace, name) = match var {
+            Variable::MultiplierLeft(i) => (self.multipliers.get(i), format!("left[{i}]")),
+            Variable::MultiplierRight(i) => (self.multipliers.get(i), format!("right[{i}]")),
+            Variable::MultiplierOutput(i) => (self.multipliers.get(i), format!("out[{i}]")),
+            Variable::Committed(i) => (self.commitments.get(i), format!("v[{i}]")),
+            Variable::One() => (None, "one".to_string()),
+            Variable::Zero() => (None, "zero".to_string()),
+        };
+
+        match namespace {
+            Some(namespace) => format!("{namespace}/{name}"),
+            None => name,
+        }
+    }
+}
+
+/// The stack of namespaces of a constraint system, the namespaces recorded so far,
+/// and the cost of each namespace
+///
+/// The stack is only kept when the `namespaces` feature is enabled; otherwise only
+/// explicitly labeled constraints are recorded, and every cost is charged to the root
+/// of the profile.
+#[derive(Clone, Debug)]
+pub(crate) struct Namespaces {
+    /// The stack of namespaces that prefix new constraints and variables
+    #[cfg(feature = "namespaces")]
+    stack: Vec<String>,
+    /// The namespaces recorded so far
+    recorded: CircuitNamespaces,
+    /// The cost of each namespace so far
+    profile: CircuitProfile,
+    /// The indices of the scopes from the root of the profile to the current scope
+    #[cfg(feature = "namespaces")]
+    cursor: Vec<usize>,
+    /// Whether the randomized phase has begun
+    randomized: bool,
+}
+
+impl Default for Namespaces {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "namespaces")]
+            stack: Vec::new(),
+            recorded: CircuitNamespaces::default(),
+            profile: CircuitProfile::new(CircuitProfile::ROOT),
+            #[cfg(feature = "namespaces")]
+            cursor: Vec::new(),
+            randomized: false,
+        }
+    }
+}
+
+#[cfg(feature = "namespaces")]
+impl Namespaces {
+    /// Push a namespace onto the stack
+    pub fn push_namespace(&mut self, name: &str) {
+        self.stack.push(name.to_string());
+        for component in name.split('/') {
+            let i = self.current_scope().child_index(component);
+            self.cursor.push(i);
+        }
+    }
+
+    /// Pop the innermost namespace off of the stack
+    pub fn pop_namespace(&mut self) {
+        if let Some(name) = self.stack.pop() {
+            let depth = self.cursor.len() - name.split('/').count();
+            self.cursor.truncate(depth);
+        }
+    }
+
+    /// The stack of namespaces, to be re-entered when running randomized constraints
+    /// specified within them
+    pub fn scope(&self) -> Vec<String> {
+        self.stack.clone()
+    }
+
+    /// The current namespace, if any
+    pub fn current(&self) -> Option<String> {
+        (!self.stack.is_empty()).then(|| self.stack.join("/"))
+    }
+
+    /// The profile of the current scope
+    fn current_scope(&mut self) -> &mut CircuitProfile {
+        self.cursor
+            .iter()
+            .fold(&mut self.profile, |scope, i| &mut scope.children[*i])
+    }
+
+    /// Add a cost to the current scope and each scope enclosing it
+    fn charge(&mut self, charge: impl Fn(&mut GadgetCost)) {
+        let mut scope = &mut self.profile;
+        charge(scope.cost_mut(self.randomized));
+        for i in self.cursor.iter() {
+            scope = &mut scope.children[*i];
+            charge(scope.cost_mut(self.randomized));
+        }
+    }
+}
+
+#[cfg(not(feature = "namespaces"))]
+impl Namespaces {
+    /// Namespaces are disabled, so they are not pushed
+    pub fn push_namespace(&mut self, _name: &str) {}
+
+    /// Namespaces are disabled, so there are none to pop
+    pub fn pop_namespace(&mut self) {}
+
+    /// Namespaces are disabled, so the scope is always empty
+    pub fn scope(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Namespaces are disabled, so there is no current namespace
+    pub fn current(&self) -> Option<String> {
+        None
+    }
+
+    /// Namespaces are disabled, so costs are only added to the root scope
+    fn charge(&mut self, charge: impl Fn(&mut GadgetCost)) {
+        charge(self.profile.cost_mut(self.randomized));
+    }
+}
+
+impl Namespaces {
+    /// Attribute subsequent costs to the randomized phase
+    pub fn begin_randomized_phase(&mut self) {
+        self.randomized = true;
+    }
+
+    /// The cost of each namespace so far
+    pub fn profile(&self) -> &CircuitProfile {
+        &self.profile
+    }
+
+    /// Push each of the namespaces of a scope
+    pub fn enter(&mut self, scope: &[String]) {
+        scope.iter().for_each(|name| self.push_namespace(name));
+    }
+
+    /// Pop each of the namespaces of a scope
+    pub fn exit(&mut self, scope: &[String]) {
+        scope.iter().for_each(|_| self.pop_namespace());
+    }
+
+    /// Record the label of the constraint at `index`
+    pub fn record_constraint(&mut self, index: usize, label: Option<&str>) {
+        self.charge(|cost| cost.constraints += 1);
+        let label = match (self.current(), label) {
+            (Some(namespace), Some(label)) => format!("{namespace}/{label}"),
+            (Some(namespace), None) => namespace,
+            (None, Some(label)) => label.to_string(),
+            (None, None) => return,
+        };
+
+        self.recorded.constraints.insert(index, label);
+    }
+
+    /// Record the namespace of the multiplication gate at `index`
+    pub fn record_multiplier(&mut self, index: usize) {
+        self.charge(|cost| cost.multipliers += 1);
+        if let Some(namespace) = self.current() {
+            self.recorded.multipliers.insert(index, namespace);
+        }
+    }
+
+    /// Record the namespace of the commitment at `index`
+    pub fn record_commitment(&mut self, index: usize) {
+        if let Some(namespace) = self.current() {
+            self.recorded.commitments.insert(index, namespace);
+        }
+    }
+
+    /// Record a call to `allocate`
+    pub fn record_allocation(&mut self) {
+        self.charge(|cost| cost.allocations += 1);
+    }
+
+    /// The namespaces recorded so far
+    pub fn recorded(&self) -> &CircuitNamespaces {
+        &self.recorded
+    }
+
+    /// Attach labels to the indices of a set of unsatisfied constraints
+    pub fn unsatisfied(
+        &self,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Vec<UnsatisfiedConstraint> {
+        indices
+            .into_iter()
+            .map(|index| UnsatisfiedConstraint {
+                index,
+                label: self.recorded.constraints.get(&index).cloned(),
+            })
+            .collect()
+    }
+
+    /// Attribute an error to the current namespace, if any
+    pub fn wrap_error(&self, error: R1CSError) -> R1CSError {
+        match self.current() {
+            Some(namespace) => R1CSError::InNamespace {
+                namespace,
+                error: Box::new(error),
+            },
+            None => error,
+        }
+    }
+}
+
+/// A namespace of a constraint system, popped when dropped
+///
+/// Dereferences to the constraint system, so that gadgets can be applied to it:
+///
+/// ```text
+/// let mut cs = cs.namespace("merkle/level3");
+/// hash_gadget(&mut *cs, left, right)?;
+/// ```
+pub struct Namespace<'a, CS: ?Sized> {
+    /// The constraint system the namespace was pushed onto
+    cs: &'a mut CS,
+    /// Pops the namespace off of the constraint system
+    pop: fn(&mut CS),
+}
+
+impl<'a, CS: ?Sized> Namespace<'a, CS> {
+    /// Wrap a constraint system that a namespace has been pushed onto
+    pub(crate) fn new(cs: &'a mut CS, pop: fn(&mut CS)) -> Self {
+        Self { cs, pop }
+    }
+}
+
+impl<'a, CS: ?Sized> Deref for Namespace<'a, CS> {
+    type Target = CS;
+
+    fn deref(&self) -> &CS {
+        self.cs
+    }
+}
+
+impl<'a, CS: ?Sized> DerefMut for Namespace<'a, CS> {
+    fn deref_mut(&mut self) -> &mut CS {
+        self.cs
+    }
+}
+
+impl<'a, CS: ?Sized> Drop for Namespace<'a, CS> {
+    fn drop(&mut self) {
+        (self.pop)(self.cs)
+    }
+}
+
 /// The interface for a constraint system, abstracting over the prover
 /// and verifier's roles.
 ///
@@ -92,9 +460,20 @@ pub trait ConstraintSystem {
     /// the constraint system
     ///
     /// Used so that the publicly-known "structure" of the constraint system
-    /// can be exported
+    /// can be exported. The weights do not carry the namespaces of the constraint
+    /// system, see [`get_namespaces`](ConstraintSystem::get_namespaces)
     fn get_weights(&self) -> CircuitWeights;
 
+    /// Get the namespaces recorded for the constraints and variables of the
+    /// constraint system, indexed in the same way as the weights
+    ///
+    /// Without the `namespaces` feature, only the labels of explicitly labeled
+    /// constraints are recorded. The serialized formats of the weights do not include
+    /// the namespaces, so they must be serialized alongside the weights to be kept
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        CircuitNamespaces::default()
+    }
+
     /// Allocate a single variable.
     ///
     /// This either allocates a new multiplier and returns its `left` variable,
@@ -145,6 +524,34 @@ pub trait ConstraintSystem {
     /// ```
     fn constrain(&mut self, lc: LinearCombination);
 
+    /// Enforce the explicit constraint that
+    /// ```text
+    /// lc = 0
+    /// ```
+    /// attaching `label` to the constraint, so that it can be identified if unsatisfied
+    fn constrain_with_label(&mut self, lc: LinearCombination, _label: &str) {
+        self.constrain(lc)
+    }
+
+    /// Push a namespace that prefixes the names of the constraints and variables
+    /// created until it is popped
+    ///
+    /// Namespaces nest, and a namespace may itself contain `/` separated components,
+    /// e.g. `merkle/level3`
+    fn push_namespace(&mut self, _name: &str) {}
+
+    /// Pop the innermost namespace
+    fn pop_namespace(&mut self) {}
+
+    /// Push a namespace, returning a guard that pops it when dropped
+    fn namespace(&mut self, name: &str) -> Namespace<'_, Self>
+    where
+        Self: Sized,
+    {
+        self.push_namespace(name);
+        Namespace::new(self, Self::pop_namespace)
+    }
+
     /// Evaluate a linear combination using the values allocated in the constraint system
     fn eval(&self, lc: &LinearCombination) -> Scalar;
 }
@@ -0,0 +1,125 @@
+//! Export of a circuit's multiplier graph in the Graphviz DOT language, for reviewing
+//! the structure of gadgets.
+
+use super::{CircuitNamespaces, CircuitWeights, PhaseSizes, SparseReducedMatrix, Variable};
+use crate::errors::R1CSError;
+
+impl CircuitWeights {
+    /// Render the multiplier graph of the circuit in the Graphviz DOT language
+    ///
+    /// Each multiplier `i` is a node with `l`, `r` and `o` ports for its
+    /// `MultiplierLeft`, `MultiplierRight` and `MultiplierOutput` variables, grouped
+    /// into a cluster for the first phase and a cluster for the randomized phase.
+    /// Committed variables are input nodes, and each constraint is a node joining the
+    /// variables it references. Edges run from the outputs and committed variables a
+    /// constraint references into the constraint, and from the constraint into the
+    /// inputs of the multipliers it references, so that the graph reads as dataflow.
+    ///
+    /// Multipliers and committed variables are named by their `namespaces`, and
+    /// constraints by their labels, when these were recorded.
+    ///
+    /// Returns an error if the weights reference variables outside of the phase sizes
+    pub fn to_dot(
+        &self,
+        phases: &PhaseSizes,
+        namespaces: &CircuitNamespaces,
+    ) -> Result<String, R1CSError> {
+        phases.check_weights(self)?;
+
+        let mut lines = vec![
+            "digraph circuit {".to_string(),
+            "  rankdir=LR;".to_string(),
+            "  node [fontname=\"monospace\"];".to_string(),
+        ];
+
+        lines.push("  subgraph cluster_commitments {".to_string());
+        lines.push("    label=\"commitments\";".to_string());
+        for j in 0..phases.m {
+            let name = namespaces.variable_name(&Variable::Committed(j));
+            lines.push(format!(
+                "    v{j} [shape=ellipse, label=\"{}\"];",
+                escape(&name)
+            ));
+        }
+        lines.push("  }".to_string());
+
+        let clusters = [
+            ("cluster_phase_one", "phase 1", 0..phases.n1),
+            (
+                "cluster_phase_two",
+                "randomized phase",
+                phases.n1..phases.n1 + phases.n2,
+            ),
+        ];
+        for (cluster, label, multipliers) in clusters {
+            if multipliers.is_empty() {
+                continue;
+            }
+
+            lines.push(format!("  subgraph {cluster} {{"));
+            lines.push(format!("    label=\"{label}\";"));
+            for i in multipliers {
+                let name = match namespaces.multipliers.get(&i) {
+                    Some(namespace) => format!("{namespace}/mul[{i}]"),
+                    None => format!("mul[{i}]"),
+                };
+                lines.push(format!(
+                    "    m{i} [shape=record, label=\"{{<l> left|<r> right}}|{}|<o> out\"];",
+                    escape_record(&name)
+                ));
+            }
+            lines.push("  }".to_string());
+        }
+
+        for q in 0..self.num_constraints() {
+            let name = match namespaces.constraints.get(&q) {
+                Some(label) => label.clone(),
+                None => format!("c[{q}]"),
+            };
+            lines.push(format!(
+                "  c{q} [shape=box, style=rounded, label=\"{}\"];",
+                escape(&name)
+            ));
+
+            let row = |matrix: &SparseReducedMatrix| {
+                matrix
+                    .0
+                    .get(q)
+                    .map(|row| row.0.iter().map(|(i, _)| *i).collect::<Vec<_>>())
+                    .unwrap_or_default()
+            };
+            for j in row(&self.w_v) {
+                lines.push(format!("  v{j} -> c{q};"));
+            }
+            for i in row(&self.w_o) {
+                lines.push(format!("  m{i}:o -> c{q};"));
+            }
+            for i in row(&self.w_l) {
+                lines.push(format!("  c{q} -> m{i}:l;"));
+            }
+            for i in row(&self.w_r) {
+                lines.push(format!("  c{q} -> m{i}:r;"));
+            }
+        }
+
+        lines.push("}".to_string());
+        Ok(lines.join("\n") + "\n")
+    }
+}
+
+/// Escape a string for use in a quoted DOT label
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escape a string for use as a field of a record label, in which braces, bars and
+/// angle brackets delimit fields
+fn escape_record(label: &str) -> String {
+    label.chars().fold(String::new(), |mut escaped, c| {
+        if matches!(c, '\\' | '"' | '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
@@ -0,0 +1,81 @@
+//! Definition of a finalized circuit, the structure of a constraint system once its
+//! randomized constraints have been specified.
+
+use super::{
+    CircuitFingerprint, CircuitNamespaces, CircuitProfile, CircuitWeights, PhaseSizes,
+    SparseReducedMatrix, SparseWeightRow,
+};
+use crate::errors::R1CSError;
+
+/// The structure of a circuit once all of its constraints, including the randomized
+/// constraints, have been specified
+///
+/// Returned by [`Prover::finalize`](super::Prover::finalize) and
+/// [`Verifier::finalize`](super::Verifier::finalize), from which the fingerprint,
+/// the DOT graph and the profile of a circuit are derived.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FinalizedCircuit {
+    /// The weights of the constraints, in the order in which they were added
+    pub weights: CircuitWeights,
+    /// The number of constraints added before the randomized phase
+    pub phase_one_constraints: usize,
+    /// The sizes of the phases of the circuit
+    pub phases: PhaseSizes,
+    /// The namespaces recorded for the constraints and variables
+    ///
+    /// Without the `namespaces` feature, only the labels of explicitly labeled
+    /// constraints are recorded
+    pub namespaces: CircuitNamespaces,
+    /// The cost of each namespace of the circuit
+    ///
+    /// Without the `namespaces` feature, the whole cost is attributed to the root scope
+    pub profile: CircuitProfile,
+}
+
+impl FinalizedCircuit {
+    /// Compute the fingerprint of the circuit
+    ///
+    /// Only the constraints added before the randomized phase are digested, see
+    /// [`CircuitFingerprint`]
+    pub fn fingerprint(&self) -> CircuitFingerprint {
+        CircuitFingerprint::new(&self.phase_one_weights(), &self.phases)
+    }
+
+    /// Render the multiplier graph of the circuit in the Graphviz DOT language
+    ///
+    /// See [`CircuitWeights::to_dot`] for the layout of the graph
+    pub fn to_dot(&self) -> Result<String, R1CSError> {
+        self.weights.to_dot(&self.phases, &self.namespaces)
+    }
+
+    /// The weights of the constraints added before the randomized phase
+    fn phase_one_weights(&self) -> CircuitWeights {
+        let q1 = self.phase_one_constraints;
+        let truncate = |matrix: &SparseReducedMatrix| {
+            SparseReducedMatrix(
+                matrix
+                    .0
+                    .iter()
+                    .take(q1)
+                    .map(|row| SparseWeightRow(row.0.clone()))
+                    .collect(),
+            )
+        };
+
+        CircuitWeights {
+            w_l: truncate(&self.weights.w_l),
+            w_r: truncate(&self.weights.w_r),
+            w_o: truncate(&self.weights.w_o),
+            w_v: truncate(&self.weights.w_v),
+            c: SparseWeightRow(
+                self.weights
+                    .c
+                    .0
+                    .iter()
+                    .filter(|(i, _)| *i < q1)
+                    .cloned()
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Definition of a circuit fingerprint, a digest of a circuit's structure that can be
+//! bound into the transcript of a proof.
+
+use merlin::HashChainTranscript as Transcript;
+use serde::{Deserialize, Serialize};
+
+use super::{CircuitWeights, PhaseSizes, SparseReducedMatrix, SparseWeightRow};
+use crate::transcript::TranscriptProtocol;
+
+/// The number of bytes in a circuit fingerprint
+pub const FINGERPRINT_BYTES: usize = 32;
+
+/// A digest of the structure of a circuit: the weights of the constraints added
+/// before the randomized phase, and the sizes of the circuit's phases.
+///
+/// The weights of randomized constraints are excluded, as they depend on challenges
+/// drawn from the transcript; those challenges are themselves bound to the fingerprint.
+/// The namespaces of the circuit are excluded too, so that renaming a gadget does not
+/// change the circuit.
+///
+/// A prover and verifier constructed with a fingerprint absorb it into the transcript
+/// before any commitments, so that the proof is bound to a single version of the
+/// circuit. Each checks that the circuit it builds matches the fingerprint, and fails
+/// with [`R1CSError::CircuitFingerprintMismatch`](super::R1CSError::CircuitFingerprintMismatch)
+/// otherwise.
+///
+/// The prover also attaches its fingerprint to the proof, see
+/// [`R1CSProof::fingerprint`](super::R1CSProof::fingerprint). A verifier rejects a proof
+/// carrying a different fingerprint than its own, e.g. one generated for a different
+/// circuit layout, before replaying the proof's transcript.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CircuitFingerprint(pub [u8; FINGERPRINT_BYTES]);
+
+impl CircuitFingerprint {
+    /// Compute the fingerprint of a circuit from the weights of its first-phase
+    /// constraints and its phase sizes
+    pub fn new(weights: &CircuitWeights, phases: &PhaseSizes) -> Self {
+        let mut transcript = Transcript::new(b"circuit fingerprint");
+        transcript.append_u64(b"n1", phases.n1 as u64);
+        transcript.append_u64(b"n2", phases.n2 as u64);
+        transcript.append_u64(b"m", phases.m as u64);
+
+        let matrices: [(&'static [u8], &SparseReducedMatrix); 4] = [
+            (b"W_L", &weights.w_l),
+            (b"W_R", &weights.w_r),
+            (b"W_O", &weights.w_o),
+            (b"W_V", &weights.w_v),
+        ];
+        for (label, matrix) in matrices {
+            transcript.append_u64(label, matrix.0.len() as u64);
+            for row in matrix.0.iter() {
+                append_row(&mut transcript, row);
+            }
+        }
+        append_row(&mut transcript, &weights.c);
+
+        let mut digest = [0u8; FINGERPRINT_BYTES];
+        transcript.challenge_bytes(b"fingerprint", &mut digest);
+        Self(digest)
+    }
+
+    /// The bytes of the fingerprint
+    pub fn as_bytes(&self) -> &[u8; FINGERPRINT_BYTES] {
+        &self.0
+    }
+}
+
+/// Absorb the entries of a sparse row of weights into a transcript
+fn append_row(transcript: &mut Transcript, row: &SparseWeightRow) {
+    transcript.append_u64(b"row", row.0.len() as u64);
+    for (i, weight) in row.0.iter() {
+        transcript.append_u64(b"i", *i as u64);
+        transcript.append_scalar(b"w", weight);
+    }
+}
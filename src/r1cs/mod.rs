@@ -2,19 +2,37 @@
 #![doc = include_str!("../../docs/cs-proof.md")]
 mod notes {}
 
+mod circuit_format;
 mod constraint_system;
+mod dot;
+mod finalized_circuit;
+mod fingerprint;
 mod linear_combination;
+mod profile;
 mod proof;
 mod prover;
+mod test_constraint_system;
+mod verification_key;
 mod verifier;
+mod witness;
 
+pub use self::circuit_format::{CircuitDimensions, CIRCUIT_FORMAT_MAGIC, CIRCUIT_FORMAT_VERSION};
+pub(crate) use self::constraint_system::Namespaces;
 pub use self::constraint_system::{
-    CircuitWeights, ConstraintSystem, RandomizableConstraintSystem, RandomizedConstraintSystem,
-    SparseReducedMatrix, SparseWeightRow,
+    CircuitNamespaces, CircuitWeights, ConstraintSystem, Namespace, RandomizableConstraintSystem,
+    RandomizedConstraintSystem, SparseReducedMatrix, SparseWeightRow, UnsatisfiedConstraint,
 };
+pub use self::finalized_circuit::FinalizedCircuit;
+pub use self::fingerprint::{CircuitFingerprint, FINGERPRINT_BYTES};
 pub use self::linear_combination::{LinearCombination, Variable};
+pub use self::profile::{CircuitProfile, GadgetCost};
 pub use self::proof::R1CSProof;
-pub use self::prover::Prover;
-pub use self::verifier::Verifier;
+pub use self::prover::{Prover, RandomizingProver};
+pub use self::test_constraint_system::{
+    PhaseCounts, RandomizingTestConstraintSystem, TestConstraintSystem,
+};
+pub use self::verification_key::{PhaseSizes, VerificationKey};
+pub use self::verifier::{RandomizingVerifier, Verifier};
+pub use self::witness::Witness;
 
 pub use crate::errors::R1CSError;
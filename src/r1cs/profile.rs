@@ -0,0 +1,133 @@
+//! Definition of a circuit profile, attributing the cost of a circuit to the
+//! namespaces of the gadgets that specify it.
+
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// The cost of a gadget scope within a single phase of a circuit
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GadgetCost {
+    /// The number of multiplication gates allocated
+    pub multipliers: usize,
+    /// The number of constraints added, including those added by `multiply`
+    pub constraints: usize,
+    /// The number of calls to [`ConstraintSystem::allocate`](super::ConstraintSystem::allocate)
+    pub allocations: usize,
+}
+
+impl GadgetCost {
+    /// Add the cost of another scope to this one
+    fn add(&self, other: &GadgetCost) -> GadgetCost {
+        GadgetCost {
+            multipliers: self.multipliers + other.multipliers,
+            constraints: self.constraints + other.constraints,
+            allocations: self.allocations + other.allocations,
+        }
+    }
+}
+
+/// The cost of a circuit, broken down hierarchically by namespace
+///
+/// Each scope's costs include those of its children. The root scope covers the
+/// whole circuit, so its multipliers determine the padded size of the proof.
+///
+/// Nested scopes are only recorded when the `namespaces` feature is enabled; without
+/// it, the profile consists of the root scope alone.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CircuitProfile {
+    /// The name of the scope, the last component of its namespace
+    pub name: String,
+    /// The cost of the scope in the first phase
+    pub phase_one: GadgetCost,
+    /// The cost of the scope in the randomized phase
+    pub phase_two: GadgetCost,
+    /// The scopes nested within this one, in the order they were first entered
+    pub children: Vec<CircuitProfile>,
+}
+
+impl CircuitProfile {
+    /// The name of the root scope
+    pub const ROOT: &'static str = "circuit";
+
+    /// Create an empty profile of a scope
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            phase_one: GadgetCost::default(),
+            phase_two: GadgetCost::default(),
+            children: Vec::new(),
+        }
+    }
+
+    /// The cost of the scope across both phases
+    pub fn total(&self) -> GadgetCost {
+        self.phase_one.add(&self.phase_two)
+    }
+
+    /// The number of multipliers allocated in the scope, padded to the next power of two
+    pub fn padded_n(&self) -> usize {
+        self.total().multipliers.next_power_of_two()
+    }
+
+    /// Look up a nested scope by its `/` separated path relative to this scope
+    pub fn scope(&self, path: &str) -> Option<&CircuitProfile> {
+        path.split('/').try_fold(self, |scope, name| {
+            scope.children.iter().find(|child| child.name == name)
+        })
+    }
+
+    /// Serialize the profile as JSON
+    pub fn to_json(&self) -> String {
+        // The profile contains only strings and integers, so serialization cannot fail
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// The cost of the given phase, for recording
+    pub(crate) fn cost_mut(&mut self, randomized: bool) -> &mut GadgetCost {
+        if randomized {
+            &mut self.phase_two
+        } else {
+            &mut self.phase_one
+        }
+    }
+
+    /// Get the index of the child scope with the given name, creating it if needed
+    pub(crate) fn child_index(&mut self, name: &str) -> usize {
+        match self.children.iter().position(|child| child.name == name) {
+            Some(i) => i,
+            None => {
+                self.children.push(CircuitProfile::new(name));
+                self.children.len() - 1
+            }
+        }
+    }
+
+    /// Write a line for this scope and each of its children, indented by depth
+    fn fmt_scope(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let total = self.total();
+        writeln!(
+            f,
+            "{:indent$}{}: {} multipliers ({} randomized), {} constraints ({} randomized), {} allocations",
+            "",
+            self.name,
+            total.multipliers,
+            self.phase_two.multipliers,
+            total.constraints,
+            self.phase_two.constraints,
+            total.allocations,
+            indent = 2 * depth,
+        )?;
+
+        self.children
+            .iter()
+            .try_for_each(|child| child.fmt_scope(f, depth + 1))
+    }
+}
+
+impl Display for CircuitProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "padded_n = {}", self.padded_n())?;
+        self.fmt_scope(f, 0)
+    }
+}
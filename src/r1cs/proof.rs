@@ -4,6 +4,7 @@
 use mpc_stark::algebra::scalar::{Scalar, SCALAR_BYTES};
 use mpc_stark::algebra::stark_curve::{StarkPoint, STARK_POINT_BYTES};
 
+use super::{CircuitFingerprint, FINGERPRINT_BYTES};
 use crate::errors::R1CSError;
 use crate::inner_product_proof::InnerProductProof;
 use crate::util;
@@ -13,6 +14,8 @@ use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 
 const ONE_PHASE_COMMITMENTS: u8 = 0;
 const TWO_PHASE_COMMITMENTS: u8 = 1;
+/// Set in the version byte when the proof carries the fingerprint of its circuit
+const WITH_FINGERPRINT: u8 = 2;
 
 /// A proof of some statement specified by a
 /// [`ConstraintSystem`](::r1cs::ConstraintSystem).
@@ -64,16 +67,25 @@ pub struct R1CSProof {
     pub e_blinding: Scalar,
     /// Proof data for the inner-product argument.
     pub ipp_proof: InnerProductProof,
+    /// The fingerprint of the circuit the proof's transcript is bound to, if any
+    ///
+    /// Verifiers compare it against their own fingerprint before replaying the
+    /// transcript, so that a proof for another circuit is rejected early.  It is a
+    /// hint only, the binding itself comes from the transcript.
+    pub fingerprint: Option<CircuitFingerprint>,
 }
 
 impl R1CSProof {
     /// Serializes the proof into a byte array of 1 version byte + \\((13 or 16) + 2k\\) 32-byte elements,
-    /// where \\(k=\lceil \log_2(n) \rceil\\) and \\(n\\) is the number of multiplication gates.
+    /// where \\(k=\lceil \log_2(n) \rceil\\) and \\(n\\) is the number of multiplication gates,
+    /// plus a 32-byte circuit fingerprint if the proof carries one.
     ///
     /// # Layout
     ///
     /// The layout of the r1cs proof encoding is:
     /// * 1 version byte indicating whether the proof contains second-phase commitments or not,
+    ///   and whether it carries a circuit fingerprint,
+    /// * the 32-byte circuit fingerprint, if any,
     /// * 8 or 11 compressed Ristretto points \\(A_{I1},A_{O1},S_1,(A_{I2},A_{O2},S_2),T_1,...,T_6\\)
     ///   (\\(A_{I2},A_{O2},S_2\\) are skipped if there were no multipliers added in the randomized phase),
     /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
@@ -81,16 +93,23 @@ impl R1CSProof {
     /// * two scalars \\(a, b\\).
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(self.serialized_size());
-        if self.missing_phase2_commitments() {
-            buf.push(ONE_PHASE_COMMITMENTS);
-            buf.extend_from_slice(&self.A_I1.to_bytes());
-            buf.extend_from_slice(&self.A_O1.to_bytes());
-            buf.extend_from_slice(&self.S1.to_bytes());
+        let phase_commitments = if self.missing_phase2_commitments() {
+            ONE_PHASE_COMMITMENTS
         } else {
-            buf.push(TWO_PHASE_COMMITMENTS);
-            buf.extend_from_slice(&self.A_I1.to_bytes());
-            buf.extend_from_slice(&self.A_O1.to_bytes());
-            buf.extend_from_slice(&self.S1.to_bytes());
+            TWO_PHASE_COMMITMENTS
+        };
+        match self.fingerprint {
+            Some(fingerprint) => {
+                buf.push(phase_commitments | WITH_FINGERPRINT);
+                buf.extend_from_slice(fingerprint.as_bytes());
+            }
+            None => buf.push(phase_commitments),
+        }
+
+        buf.extend_from_slice(&self.A_I1.to_bytes());
+        buf.extend_from_slice(&self.A_O1.to_bytes());
+        buf.extend_from_slice(&self.S1.to_bytes());
+        if phase_commitments == TWO_PHASE_COMMITMENTS {
             buf.extend_from_slice(&self.A_I2.to_bytes());
             buf.extend_from_slice(&self.A_O2.to_bytes());
             buf.extend_from_slice(&self.S2.to_bytes());
@@ -109,13 +128,18 @@ impl R1CSProof {
 
     /// Returns the size in bytes required to serialize the `R1CSProof`.
     pub fn serialized_size(&self) -> usize {
-        // version tag + (11 or 14) elements + the ipp
+        // version tag + (11 or 14) elements + the fingerprint + the ipp
         let elements = if self.missing_phase2_commitments() {
             11
         } else {
             14
         };
-        1 + elements * 32 + self.ipp_proof.serialized_size()
+        let fingerprint = if self.fingerprint.is_some() {
+            FINGERPRINT_BYTES
+        } else {
+            0
+        };
+        1 + elements * 32 + fingerprint + self.ipp_proof.serialized_size()
     }
 
     fn missing_phase2_commitments(&self) -> bool {
@@ -129,9 +153,21 @@ impl R1CSProof {
         if slice.is_empty() {
             return Err(R1CSError::FormatError);
         }
-        let version = slice[0];
+        let version = slice[0] & !WITH_FINGERPRINT;
+        let has_fingerprint = slice[0] & WITH_FINGERPRINT != 0;
         let mut slice = &slice[1..];
 
+        let fingerprint = if has_fingerprint {
+            if slice.len() < FINGERPRINT_BYTES {
+                return Err(R1CSError::FormatError);
+            }
+            let fingerprint = CircuitFingerprint(util::read_exact::<FINGERPRINT_BYTES>(slice));
+            slice = &slice[FINGERPRINT_BYTES..];
+            Some(fingerprint)
+        } else {
+            None
+        };
+
         if slice.len() % 32 != 0 {
             return Err(R1CSError::FormatError);
         }
@@ -203,6 +239,7 @@ impl R1CSProof {
             t_x_blinding,
             e_blinding,
             ipp_proof,
+            fingerprint,
         })
     }
 }
@@ -1,13 +1,13 @@
 #![allow(non_snake_case)]
 
-use itertools::Itertools;
 use merlin::HashChainTranscript as Transcript;
 use mpc_stark::algebra::scalar::Scalar;
 use mpc_stark::algebra::stark_curve::StarkPoint;
 
 use super::{
-    CircuitWeights, ConstraintSystem, LinearCombination, R1CSProof, RandomizableConstraintSystem,
-    RandomizedConstraintSystem, Variable,
+    CircuitFingerprint, CircuitNamespaces, CircuitWeights, ConstraintSystem, FinalizedCircuit,
+    LinearCombination, Namespaces, PhaseSizes, R1CSProof, RandomizableConstraintSystem,
+    RandomizedConstraintSystem, UnsatisfiedConstraint, Variable,
 };
 
 use crate::errors::R1CSError;
@@ -29,6 +29,10 @@ pub struct Prover<'t, 'g> {
     pc_gens: &'g PedersenGens,
     /// The constraints accumulated so far.
     constraints: Vec<LinearCombination>,
+    /// The namespaces of the constraints and variables
+    namespaces: Namespaces,
+    /// The fingerprint of the circuit the transcript is bound to, if any
+    fingerprint: Option<CircuitFingerprint>,
     /// Stores assignments to the "left" of multiplication gates
     a_L: Vec<Scalar>,
     /// Stores assignments to the "right" of multiplication gates
@@ -74,26 +78,11 @@ impl<'t, 'g> ConstraintSystem for Prover<'t, 'g> {
     }
 
     fn get_weights(&self) -> CircuitWeights {
-        // Extract sparse-reduced weights from each constraint to construct the matrices
-        let (w_l, w_r, w_o, w_v, c) = self
-            // It's important that this iteration is in the correct order of the constraints,
-            // otherwise we'll write the wrong index for the given constant in a constraint
-            .constraints
-            .iter()
-            .enumerate()
-            .map(|(i, lc)| {
-                let (w_l_row, w_r_row, w_o_row, w_v_row, c_i) = lc.extract_weights();
-                (w_l_row, w_r_row, w_o_row, w_v_row, (i, c_i))
-            })
-            .multiunzip();
-
-        CircuitWeights {
-            w_l,
-            w_r,
-            w_o,
-            w_v,
-            c,
-        }
+        CircuitWeights::from_constraints(&self.constraints)
+    }
+
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.namespaces.recorded().clone()
     }
 
     fn multiply(
@@ -111,6 +100,7 @@ impl<'t, 'g> ConstraintSystem for Prover<'t, 'g> {
         let r_var = Variable::MultiplierRight(self.a_R.len());
         let o_var = Variable::MultiplierOutput(self.a_O.len());
         // ... and assign them
+        self.namespaces.record_multiplier(self.a_L.len());
         self.a_L.push(l);
         self.a_R.push(r);
         self.a_O.push(o);
@@ -125,12 +115,15 @@ impl<'t, 'g> ConstraintSystem for Prover<'t, 'g> {
     }
 
     fn allocate(&mut self, assignment: Option<Scalar>) -> Result<Variable, R1CSError> {
-        let scalar = assignment.ok_or(R1CSError::MissingAssignment)?;
+        let scalar =
+            assignment.ok_or_else(|| self.namespaces.wrap_error(R1CSError::MissingAssignment))?;
+        self.namespaces.record_allocation();
 
         match self.pending_multiplier {
             None => {
                 let i = self.a_L.len();
                 self.pending_multiplier = Some(i);
+                self.namespaces.record_multiplier(i);
                 self.a_L.push(scalar);
                 self.a_R.push(Scalar::zero());
                 self.a_O.push(Scalar::zero());
@@ -149,7 +142,8 @@ impl<'t, 'g> ConstraintSystem for Prover<'t, 'g> {
         &mut self,
         input_assignments: Option<(Scalar, Scalar)>,
     ) -> Result<(Variable, Variable, Variable), R1CSError> {
-        let (l, r) = input_assignments.ok_or(R1CSError::MissingAssignment)?;
+        let (l, r) = input_assignments
+            .ok_or_else(|| self.namespaces.wrap_error(R1CSError::MissingAssignment))?;
         let o = l * r;
 
         // Create variables for l,r,o ...
@@ -157,6 +151,7 @@ impl<'t, 'g> ConstraintSystem for Prover<'t, 'g> {
         let r_var = Variable::MultiplierRight(self.a_R.len());
         let o_var = Variable::MultiplierOutput(self.a_O.len());
         // ... and assign them
+        self.namespaces.record_multiplier(self.a_L.len());
         self.a_L.push(l);
         self.a_R.push(r);
         self.a_O.push(o);
@@ -173,9 +168,25 @@ impl<'t, 'g> ConstraintSystem for Prover<'t, 'g> {
     }
 
     fn constrain(&mut self, lc: LinearCombination) {
+        self.namespaces
+            .record_constraint(self.constraints.len(), None);
+        self.constraints.push(lc);
+    }
+
+    fn constrain_with_label(&mut self, lc: LinearCombination, label: &str) {
+        self.namespaces
+            .record_constraint(self.constraints.len(), Some(label));
         self.constraints.push(lc);
     }
 
+    fn push_namespace(&mut self, name: &str) {
+        self.namespaces.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespaces.pop_namespace()
+    }
+
     fn eval(&self, lc: &LinearCombination) -> Scalar {
         lc.terms
             .iter()
@@ -201,7 +212,15 @@ impl<'t, 'g> RandomizableConstraintSystem for Prover<'t, 'g> {
     where
         F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
     {
-        self.deferred_constraints.push(Box::new(callback));
+        // Run the callback within the namespaces it was specified in
+        let scope = self.namespaces.scope();
+        self.deferred_constraints
+            .push(Box::new(move |cs: &mut RandomizingProver<'t, 'g>| {
+                cs.prover.namespaces.enter(&scope);
+                let res = callback(cs);
+                cs.prover.namespaces.exit(&scope);
+                res
+            }));
         Ok(())
     }
 }
@@ -223,6 +242,10 @@ impl<'t, 'g> ConstraintSystem for RandomizingProver<'t, 'g> {
         self.prover.get_weights()
     }
 
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.prover.get_namespaces()
+    }
+
     fn multiply(
         &mut self,
         left: LinearCombination,
@@ -250,6 +273,18 @@ impl<'t, 'g> ConstraintSystem for RandomizingProver<'t, 'g> {
         self.prover.constrain(lc)
     }
 
+    fn constrain_with_label(&mut self, lc: LinearCombination, label: &str) {
+        self.prover.constrain_with_label(lc, label)
+    }
+
+    fn push_namespace(&mut self, name: &str) {
+        self.prover.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.prover.pop_namespace()
+    }
+
     fn eval(&self, lc: &LinearCombination) -> Scalar {
         self.prover.eval(lc)
     }
@@ -291,6 +326,8 @@ impl<'t, 'g> Prover<'t, 'g> {
             v: Vec::new(),
             v_blinding: Vec::new(),
             constraints: Vec::new(),
+            namespaces: Namespaces::default(),
+            fingerprint: None,
             a_L: Vec::new(),
             a_R: Vec::new(),
             a_O: Vec::new(),
@@ -299,6 +336,27 @@ impl<'t, 'g> Prover<'t, 'g> {
         }
     }
 
+    /// Construct an empty constraint system bound to the circuit with the given
+    /// `fingerprint`.
+    ///
+    /// The fingerprint is absorbed into the transcript before any commitments, so the
+    /// proof only verifies against a verifier bound to the same circuit. Proving fails
+    /// with [`R1CSError::CircuitFingerprintMismatch`] if the circuit specified does not
+    /// match the fingerprint.
+    pub fn new_with_fingerprint(
+        pc_gens: &'g PedersenGens,
+        transcript: &'t mut Transcript,
+        fingerprint: CircuitFingerprint,
+    ) -> Self {
+        let mut prover = Self::new(pc_gens, transcript);
+        prover
+            .transcript
+            .r1cs_circuit_domain_sep(fingerprint.as_bytes());
+        prover.fingerprint = Some(fingerprint);
+
+        prover
+    }
+
     /// Creates commitment to a high-level variable and adds it to the transcript.
     ///
     /// # Inputs
@@ -318,6 +376,7 @@ impl<'t, 'g> Prover<'t, 'g> {
     /// and a [`Variable`] corresponding to it, which can be used to form constraints.
     pub fn commit(&mut self, v: Scalar, v_blinding: Scalar) -> (StarkPoint, Variable) {
         let i = self.v.len();
+        self.namespaces.record_commitment(i);
         self.v.push(v);
         self.v_blinding.push(v_blinding);
 
@@ -342,6 +401,21 @@ impl<'t, 'g> Prover<'t, 'g> {
     fn flattened_constraints(
         &mut self,
         z: &Scalar,
+    ) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Vec<Scalar>) {
+        let mut exp_z = *z;
+        self.flattened_constraints_from(z, &mut exp_z)
+    }
+
+    /// Flatten the constraints as in `flattened_constraints`, weighting the
+    /// first constraint by `exp_z` rather than by `z`.
+    ///
+    /// On return, `exp_z` holds the weight of the next constraint, so that the
+    /// constraints of several constraint systems can be flattened with distinct
+    /// powers of `z`.
+    fn flattened_constraints_from(
+        &self,
+        z: &Scalar,
+        exp_z: &mut Scalar,
     ) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Vec<Scalar>) {
         let n = self.a_L.len();
         let m = self.v.len();
@@ -351,28 +425,27 @@ impl<'t, 'g> Prover<'t, 'g> {
         let mut wO = vec![Scalar::zero(); n];
         let mut wV = vec![Scalar::zero(); m];
 
-        let mut exp_z = *z;
         for lc in self.constraints.iter() {
             for (var, coeff) in &lc.terms {
                 match var {
                     Variable::MultiplierLeft(i) => {
-                        wL[*i] += exp_z * coeff;
+                        wL[*i] += *exp_z * coeff;
                     }
                     Variable::MultiplierRight(i) => {
-                        wR[*i] += exp_z * coeff;
+                        wR[*i] += *exp_z * coeff;
                     }
                     Variable::MultiplierOutput(i) => {
-                        wO[*i] += exp_z * coeff;
+                        wO[*i] += *exp_z * coeff;
                     }
                     Variable::Committed(i) => {
-                        wV[*i] -= exp_z * coeff;
+                        wV[*i] -= *exp_z * coeff;
                     }
                     Variable::One() | Variable::Zero() => {
                         // The prover doesn't need to handle constant terms
                     }
                 }
             }
-            exp_z *= *z;
+            *exp_z *= *z;
         }
 
         (wL, wR, wO, wV)
@@ -383,10 +456,13 @@ impl<'t, 'g> Prover<'t, 'g> {
     fn create_randomized_constraints(mut self) -> Result<Self, R1CSError> {
         // Clear the pending multiplier (if any) because it was committed into A_L/A_R/S.
         self.pending_multiplier = None;
+        self.namespaces.begin_randomized_phase();
 
-        if self.deferred_constraints.is_empty() {
+        let n1 = self.a_L.len();
+        let q1 = self.constraints.len();
+        let prover = if self.deferred_constraints.is_empty() {
             self.transcript.r1cs_1phase_domain_sep();
-            Ok(self)
+            self
         } else {
             self.transcript.r1cs_2phase_domain_sep();
             // Note: the wrapper could've used &mut instead of ownership,
@@ -397,15 +473,80 @@ impl<'t, 'g> Prover<'t, 'g> {
             for callback in callbacks.drain(..) {
                 callback(&mut wrapped_self)?;
             }
-            Ok(wrapped_self.prover)
+            wrapped_self.prover
+        };
+
+        prover.check_fingerprint(n1, q1)?;
+        Ok(prover)
+    }
+
+    /// Compute the fingerprint of the circuit, given the number of multipliers and
+    /// constraints allocated in its first phase
+    fn circuit_fingerprint(&self, n1: usize, q1: usize) -> CircuitFingerprint {
+        let phases = PhaseSizes {
+            n1,
+            n2: self.a_L.len() - n1,
+            m: self.v.len(),
+        };
+        let weights = CircuitWeights::from_constraints(&self.constraints[..q1]);
+
+        CircuitFingerprint::new(&weights, &phases)
+    }
+
+    /// Check that the circuit matches the fingerprint the transcript is bound to, if any
+    fn check_fingerprint(&self, n1: usize, q1: usize) -> Result<(), R1CSError> {
+        match self.fingerprint {
+            Some(fingerprint) if fingerprint != self.circuit_fingerprint(n1, q1) => {
+                Err(R1CSError::CircuitFingerprintMismatch)
+            }
+            _ => Ok(()),
         }
     }
 
     /// Checks whether all the constraints are satisfied, does not prove the statement
     pub fn constraints_satisfied(&self) -> bool {
-        self.constraints
+        self.unsatisfied_constraints().is_empty()
+    }
+
+    /// Find the constraints that are not satisfied by the current assignments, along
+    /// with their labels
+    ///
+    /// Randomized constraints are only added when proving, so they are not checked
+    pub fn unsatisfied_constraints(&self) -> Vec<UnsatisfiedConstraint> {
+        let indices = self
+            .constraints
             .iter()
-            .all(|constraint| self.eval(constraint) == Scalar::zero())
+            .enumerate()
+            .filter(|(_, constraint)| self.eval(constraint) != Scalar::zero())
+            .map(|(i, _)| i);
+
+        self.namespaces.unsatisfied(indices)
+    }
+
+    /// Consume the prover, running its randomized constraints, and return the finished
+    /// circuit, from which its fingerprint, DOT graph and profile are derived
+    ///
+    /// Without the `namespaces` feature, the circuit records no namespaces beyond the
+    /// labels of explicitly labeled constraints, and its profile only has a root scope
+    ///
+    /// The randomized constraints draw challenges from the transcript, so the transcript
+    /// cannot be used to prove the circuit afterwards
+    pub fn finalize(self) -> Result<FinalizedCircuit, R1CSError> {
+        let n1 = self.a_L.len();
+        let q1 = self.constraints.len();
+        let prover = self.create_randomized_constraints()?;
+
+        Ok(FinalizedCircuit {
+            weights: prover.get_weights(),
+            phase_one_constraints: q1,
+            phases: PhaseSizes {
+                n1,
+                n2: prover.a_L.len() - n1,
+                m: prover.v.len(),
+            },
+            namespaces: prover.get_namespaces(),
+            profile: prover.namespaces.profile().clone(),
+        })
     }
 
     /// Consume this `ConstraintSystem` to produce a proof.
@@ -723,6 +864,422 @@ impl<'t, 'g> Prover<'t, 'g> {
             t_x_blinding,
             e_blinding,
             ipp_proof,
+            fingerprint: self.fingerprint,
         })
     }
+
+    /// Consume a set of constraint systems, one for each of `k` circuit
+    /// instances, to produce a single proof of all of them.
+    ///
+    /// The `j`th instance is proved over the generators `bp_gens.share(j)`, so
+    /// the [`BulletproofGens`] should have `party_capacity` at least `k` rounded
+    /// up to the next power of two, and `gens_capacity` at least the number of
+    /// multipliers in the largest instance rounded up to the next power of two.
+    ///
+    /// Each instance's own transcript binds its statement and the challenges of
+    /// its randomized constraints, while `transcript` binds the aggregated proof.
+    /// The instances should be constructed with the same Pedersen generators.
+    pub fn prove_aggregated(
+        mut provers: Vec<Self>,
+        transcript: &mut Transcript,
+        bp_gens: &BulletproofGens,
+    ) -> Result<R1CSProof, R1CSError> {
+        use crate::util;
+        use std::iter;
+
+        if provers.is_empty() {
+            return Err(R1CSError::InvalidAggregation);
+        }
+
+        // Pad the number of instances to a power of two with empty instances
+        let k = provers.len();
+        let padded_k = k.next_power_of_two();
+        if bp_gens.party_capacity < padded_k {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let pc_gens = provers[0].pc_gens;
+        transcript.r1cs_aggregated_domain_sep(k as u64);
+
+        // Bind each instance's statement to the aggregated transcript, see
+        // `prove` for the length suffix
+        for prover in provers.iter_mut() {
+            prover.transcript.append_u64(b"m", prover.v.len() as u64);
+            let instance_challenge = prover.transcript.challenge_scalar(b"instance");
+            transcript.append_scalar(b"instance", &instance_challenge);
+        }
+
+        // Rekey the RNG with the high-level witness data of every instance
+        let mut rng = {
+            let mut builder = transcript.build_rng();
+
+            // Commit the blinding factors for the input wires
+            for v_b in provers.iter().flat_map(|prover| prover.v_blinding.iter()) {
+                builder = builder.rekey_with_witness_bytes(b"v_blinding", &v_b.to_bytes_be());
+            }
+
+            use rand::thread_rng;
+            builder.finalize(&mut thread_rng())
+        };
+
+        // Commit to the first-phase low-level witness variables of every instance
+        let n1: Vec<usize> = provers.iter().map(|prover| prover.a_L.len()).collect();
+        if n1.iter().any(|n1_j| bp_gens.gens_capacity < *n1_j) {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let i_blinding1 = Scalar::random(&mut rng);
+        let o_blinding1 = Scalar::random(&mut rng);
+        let s_blinding1 = Scalar::random(&mut rng);
+
+        let s_L1: Vec<Vec<Scalar>> = n1
+            .iter()
+            .map(|n1_j| (0..*n1_j).map(|_| Scalar::random(&mut rng)).collect())
+            .collect();
+        let s_R1: Vec<Vec<Scalar>> = n1
+            .iter()
+            .map(|n1_j| (0..*n1_j).map(|_| Scalar::random(&mut rng)).collect())
+            .collect();
+
+        // The generators of each instance's first-phase multipliers
+        let G1 = || {
+            n1.iter()
+                .enumerate()
+                .flat_map(|(j, n1_j)| bp_gens.share(j).G(*n1_j))
+        };
+        let H1 = || {
+            n1.iter()
+                .enumerate()
+                .flat_map(|(j, n1_j)| bp_gens.share(j).H(*n1_j))
+        };
+
+        // A_I = sum_j <a_L_j, G_j> + <a_R_j, H_j> + i_blinding * B_blinding
+        let A_I1 = StarkPoint::msm_iter(
+            iter::once(&i_blinding1)
+                .chain(provers.iter().flat_map(|prover| prover.a_L.iter()))
+                .chain(provers.iter().flat_map(|prover| prover.a_R.iter()))
+                .copied(),
+            iter::once(&pc_gens.B_blinding)
+                .chain(G1())
+                .chain(H1())
+                .copied(),
+        );
+
+        // A_O = sum_j <a_O_j, G_j> + o_blinding * B_blinding
+        let A_O1 = StarkPoint::msm_iter(
+            iter::once(&o_blinding1)
+                .chain(provers.iter().flat_map(|prover| prover.a_O.iter()))
+                .copied(),
+            iter::once(&pc_gens.B_blinding).chain(G1()).copied(),
+        );
+
+        // S = sum_j <s_L_j, G_j> + <s_R_j, H_j> + s_blinding * B_blinding
+        let S1 = StarkPoint::msm_iter(
+            iter::once(&s_blinding1)
+                .chain(s_L1.iter().flatten())
+                .chain(s_R1.iter().flatten())
+                .copied(),
+            iter::once(&pc_gens.B_blinding)
+                .chain(G1())
+                .chain(H1())
+                .copied(),
+        );
+
+        transcript.append_point(b"A_I1", &A_I1);
+        transcript.append_point(b"A_O1", &A_O1);
+        transcript.append_point(b"S1", &S1);
+
+        // Bind the randomized constraints of each instance to the first-phase
+        // commitments, then process the remaining constraints
+        let provers = provers
+            .into_iter()
+            .map(|mut prover| {
+                prover.transcript.append_point(b"A_I1", &A_I1);
+                prover.transcript.append_point(b"A_O1", &A_O1);
+                prover.transcript.append_point(b"S1", &S1);
+                prover.create_randomized_constraints()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Pad every instance to the size of the largest instance, rounded up to
+        // the next power of two
+        let n: Vec<usize> = provers.iter().map(|prover| prover.a_L.len()).collect();
+        let padded_n = n.iter().copied().max().unwrap_or(0).next_power_of_two();
+        let padded_size = padded_k * padded_n;
+
+        if bp_gens.gens_capacity < padded_n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        // Commit to the second-phase low-level witness variables of every instance
+
+        let has_2nd_phase_commitments = n.iter().zip(n1.iter()).any(|(n_j, n1_j)| n_j > n1_j);
+
+        let (i_blinding2, o_blinding2, s_blinding2) = if has_2nd_phase_commitments {
+            (
+                Scalar::random(&mut rng),
+                Scalar::random(&mut rng),
+                Scalar::random(&mut rng),
+            )
+        } else {
+            (Scalar::zero(), Scalar::zero(), Scalar::zero())
+        };
+
+        let s_L2: Vec<Vec<Scalar>> = n
+            .iter()
+            .zip(n1.iter())
+            .map(|(n_j, n1_j)| (*n1_j..*n_j).map(|_| Scalar::random(&mut rng)).collect())
+            .collect();
+        let s_R2: Vec<Vec<Scalar>> = n
+            .iter()
+            .zip(n1.iter())
+            .map(|(n_j, n1_j)| (*n1_j..*n_j).map(|_| Scalar::random(&mut rng)).collect())
+            .collect();
+
+        // The generators of each instance's second-phase multipliers
+        let G2 = || {
+            n.iter()
+                .zip(n1.iter())
+                .enumerate()
+                .flat_map(|(j, (n_j, n1_j))| bp_gens.share(j).G(*n_j).skip(*n1_j))
+        };
+        let H2 = || {
+            n.iter()
+                .zip(n1.iter())
+                .enumerate()
+                .flat_map(|(j, (n_j, n1_j))| bp_gens.share(j).H(*n_j).skip(*n1_j))
+        };
+
+        let (A_I2, A_O2, S2) = if has_2nd_phase_commitments {
+            (
+                // A_I = sum_j <a_L_j, G_j> + <a_R_j, H_j> + i_blinding * B_blinding
+                StarkPoint::msm_iter(
+                    iter::once(&i_blinding2)
+                        .chain(
+                            provers
+                                .iter()
+                                .zip(n1.iter())
+                                .flat_map(|(prover, n1_j)| prover.a_L.iter().skip(*n1_j)),
+                        )
+                        .chain(
+                            provers
+                                .iter()
+                                .zip(n1.iter())
+                                .flat_map(|(prover, n1_j)| prover.a_R.iter().skip(*n1_j)),
+                        )
+                        .copied(),
+                    iter::once(&pc_gens.B_blinding)
+                        .chain(G2())
+                        .chain(H2())
+                        .copied(),
+                ),
+                // A_O = sum_j <a_O_j, G_j> + o_blinding * B_blinding
+                StarkPoint::msm_iter(
+                    iter::once(&o_blinding2)
+                        .chain(
+                            provers
+                                .iter()
+                                .zip(n1.iter())
+                                .flat_map(|(prover, n1_j)| prover.a_O.iter().skip(*n1_j)),
+                        )
+                        .copied(),
+                    iter::once(&pc_gens.B_blinding).chain(G2()).copied(),
+                ),
+                // S = sum_j <s_L_j, G_j> + <s_R_j, H_j> + s_blinding * B_blinding
+                StarkPoint::msm_iter(
+                    iter::once(&s_blinding2)
+                        .chain(s_L2.iter().flatten())
+                        .chain(s_R2.iter().flatten())
+                        .copied(),
+                    iter::once(&pc_gens.B_blinding)
+                        .chain(G2())
+                        .chain(H2())
+                        .copied(),
+                ),
+            )
+        } else {
+            // See `prove`, the commitments to no variables with zero blinding
+            // factors are the identity
+            (
+                StarkPoint::identity(),
+                StarkPoint::identity(),
+                StarkPoint::identity(),
+            )
+        };
+
+        transcript.append_point(b"A_I2", &A_I2);
+        transcript.append_point(b"A_O2", &A_O2);
+        transcript.append_point(b"S2", &S2);
+
+        // Compute blinded vector polynomials l(x) and r(x) over the concatenation
+        // of the instances, each padded to `padded_n` multipliers
+
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+
+        // Each instance's constraints are weighted by the powers of `z` following
+        // those of the previous instance's constraints
+        let mut exp_z = z;
+        let flattened: Vec<_> = provers
+            .iter()
+            .map(|prover| prover.flattened_constraints_from(&z, &mut exp_z))
+            .collect();
+
+        let y_inv = y.inverse();
+        let exp_y = util::exp_iter(y).take(padded_size).collect::<Vec<_>>();
+        let exp_y_inv = util::exp_iter(y_inv).take(padded_size).collect::<Vec<_>>();
+
+        let mut l_poly = util::VecPoly3::zero(padded_size);
+        let mut r_poly = util::VecPoly3::zero(padded_size);
+
+        // Padding multipliers, including those of the empty instances, are assigned
+        // zero and are unconstrained, so they only contribute the -y^n term
+        for (r_0, y_i) in r_poly.0.iter_mut().zip(exp_y.iter()) {
+            *r_0 = -*y_i;
+        }
+
+        for (j, (prover, (wL, wR, wO, _))) in provers.iter().zip(flattened.iter()).enumerate() {
+            let offset = j * padded_n;
+            let sLsR = s_L1[j]
+                .iter()
+                .chain(s_L2[j].iter())
+                .zip(s_R1[j].iter().chain(s_R2[j].iter()));
+            for (i, (sl, sr)) in sLsR.enumerate() {
+                let g = offset + i;
+                // l_poly.1 = a_L + y^-n * (z * z^Q * W_R)
+                l_poly.1[g] = prover.a_L[i] + exp_y_inv[g] * wR[i];
+                // l_poly.2 = a_O
+                l_poly.2[g] = prover.a_O[i];
+                // l_poly.3 = s_L
+                l_poly.3[g] = *sl;
+                // r_poly.0 = (z * z^Q * W_O) - y^n
+                r_poly.0[g] += wO[i];
+                // r_poly.1 = y^n * a_R + (z * z^Q * W_L)
+                r_poly.1[g] = exp_y[g] * prover.a_R[i] + wL[i];
+                // r_poly.3 = y^n * s_R
+                r_poly.3[g] = exp_y[g] * sr;
+            }
+        }
+
+        let t_poly = util::VecPoly3::special_inner_product(&l_poly, &r_poly);
+
+        let t_1_blinding = Scalar::random(&mut rng);
+        let t_3_blinding = Scalar::random(&mut rng);
+        let t_4_blinding = Scalar::random(&mut rng);
+        let t_5_blinding = Scalar::random(&mut rng);
+        let t_6_blinding = Scalar::random(&mut rng);
+
+        let T_1 = pc_gens.commit(t_poly.t1, t_1_blinding);
+        let T_3 = pc_gens.commit(t_poly.t3, t_3_blinding);
+        let T_4 = pc_gens.commit(t_poly.t4, t_4_blinding);
+        let T_5 = pc_gens.commit(t_poly.t5, t_5_blinding);
+        let T_6 = pc_gens.commit(t_poly.t6, t_6_blinding);
+
+        transcript.append_point(b"T_1", &T_1);
+        transcript.append_point(b"T_3", &T_3);
+        transcript.append_point(b"T_4", &T_4);
+        transcript.append_point(b"T_5", &T_5);
+        transcript.append_point(b"T_6", &T_6);
+
+        let u = transcript.challenge_scalar(b"u");
+        let x = transcript.challenge_scalar(b"x");
+
+        // t_2_blinding = sum_j <z*z^Q, W_V_j * v_blinding_j>
+        let t_2_blinding = flattened
+            .iter()
+            .zip(provers.iter())
+            .flat_map(|((_, _, _, wV), prover)| wV.iter().zip(prover.v_blinding.iter()))
+            .map(|(c, v_blinding)| c * v_blinding)
+            .sum();
+
+        let t_blinding_poly = util::Poly6 {
+            t1: t_1_blinding,
+            t2: t_2_blinding,
+            t3: t_3_blinding,
+            t4: t_4_blinding,
+            t5: t_5_blinding,
+            t6: t_6_blinding,
+        };
+
+        let t_x = t_poly.eval(x);
+        let t_x_blinding = t_blinding_poly.eval(x);
+        let l_vec = l_poly.eval(x);
+        let r_vec = r_poly.eval(x);
+
+        let i_blinding = i_blinding1 + u * i_blinding2;
+        let o_blinding = o_blinding1 + u * o_blinding2;
+        let s_blinding = s_blinding1 + u * s_blinding2;
+
+        let e_blinding = x * (i_blinding + x * (o_blinding + x * s_blinding));
+
+        transcript.append_scalar(b"t_x", &t_x);
+        transcript.append_scalar(b"t_x_blinding", &t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &e_blinding);
+
+        // Get a challenge value to combine statements for the IPP
+        let w = transcript.challenge_scalar(b"w");
+        let Q = w * pc_gens.B;
+
+        let G_factors = aggregated_G_factors(&n1, padded_k, padded_n, u);
+        let H_factors = exp_y_inv
+            .into_iter()
+            .zip(G_factors.iter())
+            .map(|(y, u_or_1)| y * u_or_1)
+            .collect::<Vec<_>>();
+
+        let ipp_proof = InnerProductProof::create(
+            transcript,
+            &Q,
+            &G_factors,
+            &H_factors,
+            bp_gens.G(padded_n, padded_k).cloned().collect(),
+            bp_gens.H(padded_n, padded_k).cloned().collect(),
+            l_vec,
+            r_vec,
+        );
+
+        Ok(R1CSProof {
+            A_I1,
+            A_O1,
+            S1,
+            A_I2,
+            A_O2,
+            S2,
+            T_1,
+            T_3,
+            T_4,
+            T_5,
+            T_6,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+            fingerprint: None,
+        })
+    }
+}
+
+/// Compute the factors applied to the `G` generators in an aggregated proof
+/// over `padded_k` instances of `padded_n` multipliers each, given the number
+/// of first-phase multipliers `n1` of each instance.
+///
+/// First-phase multipliers are weighted by one, and the remaining multipliers
+/// of each instance, including padding, by the challenge `u`.
+pub(super) fn aggregated_G_factors(
+    n1: &[usize],
+    padded_k: usize,
+    padded_n: usize,
+    u: Scalar,
+) -> Vec<Scalar> {
+    use std::iter;
+
+    n1.iter()
+        .copied()
+        .chain(iter::repeat(0).take(padded_k - n1.len()))
+        .flat_map(|n1_j| {
+            iter::repeat(Scalar::one())
+                .take(n1_j)
+                .chain(iter::repeat(u).take(padded_n - n1_j))
+        })
+        .collect()
 }
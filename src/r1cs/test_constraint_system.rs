@@ -0,0 +1,457 @@
+//! Definition of a constraint system for unit testing gadgets, which checks the
+//! witness as constraints are added rather than building a proof.
+
+#![allow(non_snake_case)]
+
+use std::collections::HashMap;
+
+use merlin::HashChainTranscript as Transcript;
+use mpc_stark::algebra::scalar::Scalar;
+
+use super::{
+    CircuitNamespaces, CircuitProfile, CircuitWeights, ConstraintSystem, LinearCombination,
+    Namespaces, PhaseSizes, RandomizableConstraintSystem, RandomizedConstraintSystem,
+    UnsatisfiedConstraint, Variable,
+};
+use crate::errors::R1CSError;
+use crate::transcript::TranscriptProtocol;
+
+/// The number of multipliers and constraints allocated in a phase of a circuit
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhaseCounts {
+    /// The number of multiplication gates allocated in the phase
+    pub multipliers: usize,
+    /// The number of constraints added in the phase
+    pub constraints: usize,
+}
+
+/// A [`ConstraintSystem`] for unit testing gadgets, without generators or proofs.
+///
+/// Each constraint is evaluated against the assignments as soon as it is added.
+/// A constraint that refers to the unassigned half of a multiplier allocated by
+/// [`ConstraintSystem::allocate`] is evaluated once the multiplier is completed, or
+/// at the end of the phase.
+///
+/// Randomized constraints are deferred as they are by the prover, and are run by
+/// [`TestConstraintSystem::finalize`]. The challenges they draw can be fixed with
+/// [`TestConstraintSystem::set_challenge`]; challenges that are not fixed are drawn
+/// from a transcript, so that they are deterministic across runs.
+pub struct TestConstraintSystem {
+    transcript: Transcript,
+    /// The constraints accumulated so far.
+    constraints: Vec<LinearCombination>,
+    /// The namespaces of the constraints and variables
+    namespaces: Namespaces,
+    /// The indices of the constraints found to be unsatisfied
+    unsatisfied: Vec<usize>,
+    /// The indices of the constraints waiting on the pending multiplier
+    pending_checks: Vec<usize>,
+    /// Stores assignments to the "left" of multiplication gates
+    a_L: Vec<Scalar>,
+    /// Stores assignments to the "right" of multiplication gates
+    a_R: Vec<Scalar>,
+    /// Stores assignments to the "output" of multiplication gates
+    a_O: Vec<Scalar>,
+    /// Stores assignments to the committed variables
+    v: Vec<Scalar>,
+    /// The challenges fixed by the test, by label
+    challenges: HashMap<&'static [u8], Scalar>,
+
+    /// This list holds closures that will be called in the second phase of the protocol.
+    #[allow(clippy::type_complexity)]
+    deferred_constraints:
+        Vec<Box<dyn Fn(&mut RandomizingTestConstraintSystem) -> Result<(), R1CSError>>>,
+
+    /// Index of a pending multiplier that's not fully assigned yet.
+    pending_multiplier: Option<usize>,
+    /// The counts of the first phase, recorded once the randomized constraints are run
+    phase_one: Option<PhaseCounts>,
+}
+
+/// Test constraint system in the randomizing phase.
+///
+/// This type can only be used within the callback provided to
+/// `specify_randomized_constraints`.
+pub struct RandomizingTestConstraintSystem {
+    cs: TestConstraintSystem,
+}
+
+impl Default for TestConstraintSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConstraintSystem for TestConstraintSystem {
+    fn transcript(&mut self) -> &mut Transcript {
+        &mut self.transcript
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    fn num_multipliers(&self) -> usize {
+        self.a_O.len()
+    }
+
+    fn get_weights(&self) -> CircuitWeights {
+        CircuitWeights::from_constraints(&self.constraints)
+    }
+
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.namespaces.recorded().clone()
+    }
+
+    fn multiply(
+        &mut self,
+        mut left: LinearCombination,
+        mut right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        let l = self.eval(&left);
+        let r = self.eval(&right);
+
+        let (l_var, r_var, o_var) = self.push_multiplier(l, r);
+
+        left.add_term(l_var, -Scalar::one());
+        right.add_term(r_var, -Scalar::one());
+        self.constrain(left);
+        self.constrain(right);
+
+        (l_var, r_var, o_var)
+    }
+
+    fn allocate(&mut self, assignment: Option<Scalar>) -> Result<Variable, R1CSError> {
+        let scalar =
+            assignment.ok_or_else(|| self.namespaces.wrap_error(R1CSError::MissingAssignment))?;
+        self.namespaces.record_allocation();
+
+        match self.pending_multiplier {
+            None => {
+                let (l_var, ..) = self.push_multiplier(scalar, Scalar::zero());
+                self.pending_multiplier = Some(self.a_L.len() - 1);
+                Ok(l_var)
+            }
+            Some(i) => {
+                self.a_R[i] = scalar;
+                self.a_O[i] = self.a_L[i] * self.a_R[i];
+                self.complete_pending_multiplier();
+                Ok(Variable::MultiplierRight(i))
+            }
+        }
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        input_assignments: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        let (l, r) = input_assignments
+            .ok_or_else(|| self.namespaces.wrap_error(R1CSError::MissingAssignment))?;
+
+        Ok(self.push_multiplier(l, r))
+    }
+
+    fn commit_public(&mut self, v: Scalar) -> Variable {
+        self.commit(v)
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        self.namespaces
+            .record_constraint(self.constraints.len(), None);
+        self.check_constraint(lc);
+    }
+
+    fn constrain_with_label(&mut self, lc: LinearCombination, label: &str) {
+        self.namespaces
+            .record_constraint(self.constraints.len(), Some(label));
+        self.check_constraint(lc);
+    }
+
+    fn push_namespace(&mut self, name: &str) {
+        self.namespaces.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespaces.pop_namespace()
+    }
+
+    fn eval(&self, lc: &LinearCombination) -> Scalar {
+        lc.terms
+            .iter()
+            .map(|(var, coeff)| {
+                coeff
+                    * match var {
+                        Variable::MultiplierLeft(i) => self.a_L[*i],
+                        Variable::MultiplierRight(i) => self.a_R[*i],
+                        Variable::MultiplierOutput(i) => self.a_O[*i],
+                        Variable::Committed(i) => self.v[*i],
+                        Variable::One() => Scalar::one(),
+                        Variable::Zero() => Scalar::zero(),
+                    }
+            })
+            .sum()
+    }
+}
+
+impl RandomizableConstraintSystem for TestConstraintSystem {
+    type RandomizedCS = RandomizingTestConstraintSystem;
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        // Run the callback within the namespaces it was specified in
+        let scope = self.namespaces.scope();
+        self.deferred_constraints.push(Box::new(
+            move |cs: &mut RandomizingTestConstraintSystem| {
+                cs.cs.namespaces.enter(&scope);
+                let res = callback(cs);
+                cs.cs.namespaces.exit(&scope);
+                res
+            },
+        ));
+        Ok(())
+    }
+}
+
+impl ConstraintSystem for RandomizingTestConstraintSystem {
+    fn transcript(&mut self) -> &mut Transcript {
+        self.cs.transcript()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.cs.num_constraints()
+    }
+
+    fn num_multipliers(&self) -> usize {
+        self.cs.num_multipliers()
+    }
+
+    fn get_weights(&self) -> CircuitWeights {
+        self.cs.get_weights()
+    }
+
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.cs.get_namespaces()
+    }
+
+    fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        self.cs.multiply(left, right)
+    }
+
+    fn allocate(&mut self, assignment: Option<Scalar>) -> Result<Variable, R1CSError> {
+        self.cs.allocate(assignment)
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        input_assignments: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        self.cs.allocate_multiplier(input_assignments)
+    }
+
+    fn commit_public(&mut self, value: Scalar) -> Variable {
+        self.cs.commit_public(value)
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        self.cs.constrain(lc)
+    }
+
+    fn constrain_with_label(&mut self, lc: LinearCombination, label: &str) {
+        self.cs.constrain_with_label(lc, label)
+    }
+
+    fn push_namespace(&mut self, name: &str) {
+        self.cs.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.cs.pop_namespace()
+    }
+
+    fn eval(&self, lc: &LinearCombination) -> Scalar {
+        self.cs.eval(lc)
+    }
+}
+
+impl RandomizedConstraintSystem for RandomizingTestConstraintSystem {
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        match self.cs.challenges.get(label) {
+            Some(challenge) => *challenge,
+            None => self.cs.transcript.challenge_scalar(label),
+        }
+    }
+}
+
+impl TestConstraintSystem {
+    /// Construct an empty test constraint system
+    pub fn new() -> Self {
+        let mut transcript = Transcript::new(b"TestConstraintSystem");
+        transcript.r1cs_domain_sep();
+
+        TestConstraintSystem {
+            transcript,
+            constraints: Vec::new(),
+            namespaces: Namespaces::default(),
+            unsatisfied: Vec::new(),
+            pending_checks: Vec::new(),
+            a_L: Vec::new(),
+            a_R: Vec::new(),
+            a_O: Vec::new(),
+            v: Vec::new(),
+            challenges: HashMap::new(),
+            deferred_constraints: Vec::new(),
+            pending_multiplier: None,
+            phase_one: None,
+        }
+    }
+
+    /// Fix the value returned for challenges drawn with `label` in the randomized phase
+    pub fn set_challenge(&mut self, label: &'static [u8], challenge: Scalar) {
+        self.challenges.insert(label, challenge);
+    }
+
+    /// Allocate a high-level variable assigned to `v`
+    ///
+    /// No commitment is computed, so no blinding factor is needed
+    pub fn commit(&mut self, v: Scalar) -> Variable {
+        let i = self.v.len();
+        self.namespaces.record_commitment(i);
+        self.v.push(v);
+
+        Variable::Committed(i)
+    }
+
+    /// End the first phase and run the randomized constraints
+    ///
+    /// Subsequent calls run any randomized constraints specified since, but the
+    /// phase counts are recorded by the first call only
+    pub fn finalize(&mut self) -> Result<(), R1CSError> {
+        // The right of a pending multiplier is left assigned to zero, as by the prover
+        self.complete_pending_multiplier();
+        if self.phase_one.is_none() {
+            self.phase_one = Some(self.counts());
+        }
+        self.namespaces.begin_randomized_phase();
+
+        // Move the constraint system into the randomizing wrapper and back, as the
+        // prover does
+        let mut wrapped_self = RandomizingTestConstraintSystem {
+            cs: std::mem::take(self),
+        };
+        let callbacks = std::mem::take(&mut wrapped_self.cs.deferred_constraints);
+        let res = callbacks
+            .iter()
+            .try_for_each(|callback| callback(&mut wrapped_self));
+        *self = wrapped_self.cs;
+
+        self.complete_pending_multiplier();
+        res
+    }
+
+    /// Checks whether all the constraints added so far are satisfied
+    pub fn is_satisfied(&self) -> bool {
+        self.unsatisfied.is_empty()
+    }
+
+    /// The constraints found to be unsatisfied so far, along with their labels
+    ///
+    /// Constraints waiting on a pending multiplier are not included until they are
+    /// evaluated
+    pub fn unsatisfied_constraints(&self) -> Vec<UnsatisfiedConstraint> {
+        self.namespaces
+            .unsatisfied(self.unsatisfied.iter().copied())
+    }
+
+    /// The number of multipliers and constraints allocated in the first phase
+    ///
+    /// Before [`TestConstraintSystem::finalize`] is called, these are the current totals
+    pub fn phase_one_counts(&self) -> PhaseCounts {
+        self.phase_one.unwrap_or_else(|| self.counts())
+    }
+
+    /// The number of multipliers and constraints allocated by randomized constraints
+    pub fn phase_two_counts(&self) -> PhaseCounts {
+        match self.phase_one {
+            Some(phase_one) => PhaseCounts {
+                multipliers: self.num_multipliers() - phase_one.multipliers,
+                constraints: self.num_constraints() - phase_one.constraints,
+            },
+            None => PhaseCounts::default(),
+        }
+    }
+
+    /// The cost of each namespace of the circuit so far
+    pub fn profile(&self) -> &CircuitProfile {
+        self.namespaces.profile()
+    }
+
+    /// The phase sizes of the circuit, e.g. for building a
+    /// [`VerificationKey`](super::VerificationKey) from its weights
+    pub fn phase_sizes(&self) -> PhaseSizes {
+        PhaseSizes {
+            n1: self.phase_one_counts().multipliers,
+            n2: self.phase_two_counts().multipliers,
+            m: self.v.len(),
+        }
+    }
+
+    /// The current totals of multipliers and constraints
+    fn counts(&self) -> PhaseCounts {
+        PhaseCounts {
+            multipliers: self.num_multipliers(),
+            constraints: self.num_constraints(),
+        }
+    }
+
+    /// Assign a new multiplier
+    fn push_multiplier(&mut self, l: Scalar, r: Scalar) -> (Variable, Variable, Variable) {
+        let i = self.a_L.len();
+        self.namespaces.record_multiplier(i);
+        self.a_L.push(l);
+        self.a_R.push(r);
+        self.a_O.push(l * r);
+
+        (
+            Variable::MultiplierLeft(i),
+            Variable::MultiplierRight(i),
+            Variable::MultiplierOutput(i),
+        )
+    }
+
+    /// Add a constraint, evaluating it unless it refers to the pending multiplier
+    fn check_constraint(&mut self, lc: LinearCombination) {
+        let index = self.constraints.len();
+        let waits_on_pending = match self.pending_multiplier {
+            Some(i) => lc.terms.iter().any(|(var, _)| {
+                matches!(var, Variable::MultiplierRight(j) | Variable::MultiplierOutput(j) if *j == i)
+            }),
+            None => false,
+        };
+
+        if waits_on_pending {
+            self.pending_checks.push(index);
+        } else if self.eval(&lc) != Scalar::zero() {
+            self.unsatisfied.push(index);
+        }
+
+        self.constraints.push(lc);
+    }
+
+    /// Evaluate the constraints that were waiting on the pending multiplier
+    fn complete_pending_multiplier(&mut self) {
+        self.pending_multiplier = None;
+        for index in std::mem::take(&mut self.pending_checks) {
+            if self.eval(&self.constraints[index]) != Scalar::zero() {
+                self.unsatisfied.push(index);
+            }
+        }
+
+        self.unsatisfied.sort_unstable();
+    }
+}
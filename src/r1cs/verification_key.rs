@@ -0,0 +1,151 @@
+//! Definition of a verification key, a precompiled representation of a circuit
+//! used to verify proofs without executing gadget code.
+
+use merlin::HashChainTranscript as Transcript;
+use mpc_stark::algebra::stark_curve::StarkPoint;
+use serde::{Deserialize, Serialize};
+
+use super::{CircuitWeights, ConstraintSystem, R1CSProof, RandomizableConstraintSystem, Verifier};
+use crate::errors::R1CSError;
+use crate::generators::{BulletproofGens, PedersenGens};
+
+/// The sizes of the phases of a circuit, needed alongside its weights to replay
+/// the transcript of a proof.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhaseSizes {
+    /// The number of multipliers allocated before the randomized phase
+    pub n1: usize,
+    /// The number of multipliers allocated in the randomized phase
+    pub n2: usize,
+    /// The number of high-level variables committed to by the prover
+    pub m: usize,
+}
+
+impl PhaseSizes {
+    /// Check that the weights of a circuit only reference variables within
+    /// the phase sizes.
+    pub(crate) fn check_weights(&self, weights: &CircuitWeights) -> Result<(), R1CSError> {
+        let n = self.n1 + self.n2;
+        let multipliers_in_range = [&weights.w_l, &weights.w_r, &weights.w_o]
+            .iter()
+            .flat_map(|matrix| matrix.0.iter())
+            .flat_map(|row| row.0.iter())
+            .all(|(i, _)| *i < n);
+        let commitments_in_range = weights
+            .w_v
+            .0
+            .iter()
+            .flat_map(|row| row.0.iter())
+            .all(|(i, _)| *i < self.m);
+
+        if multipliers_in_range && commitments_in_range {
+            Ok(())
+        } else {
+            Err(R1CSError::GadgetError {
+                description: "circuit weights reference variables outside of the phase sizes"
+                    .to_string(),
+            })
+        }
+    }
+}
+
+/// A verification key for a circuit, built once from the circuit's weights.
+///
+/// Verifying against a key replays the transcript of a proof and checks it
+/// against the weights directly, without running the gadget code that
+/// specified the circuit.  Keys can be serialized, so that circuits may be
+/// shipped to verifiers as data.
+///
+/// The weights of a circuit are static, so a key cannot represent randomized
+/// constraints whose weights depend on challenge scalars.  Multipliers
+/// allocated in the randomized phase are supported, so long as their
+/// constraints do not draw challenges from the transcript.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct VerificationKey {
+    /// The weights of the circuit's constraints
+    weights: CircuitWeights,
+    /// The sizes of the circuit's phases
+    phases: PhaseSizes,
+}
+
+impl VerificationKey {
+    /// Construct a verification key from the weights of a circuit, e.g. as
+    /// exported by [`ConstraintSystem::get_weights`], and its phase sizes.
+    pub fn new(weights: CircuitWeights, phases: PhaseSizes) -> Result<Self, R1CSError> {
+        phases.check_weights(&weights)?;
+        Ok(Self { weights, phases })
+    }
+
+    /// The weights of the circuit
+    pub fn weights(&self) -> &CircuitWeights {
+        &self.weights
+    }
+
+    /// The sizes of the circuit's phases
+    pub fn phases(&self) -> PhaseSizes {
+        self.phases
+    }
+
+    /// Verify `proof` against the key, given the commitments to the circuit's
+    /// high-level variables.
+    ///
+    /// The `transcript` must be in the same state as the prover's transcript
+    /// when the prover's constraint system was constructed.
+    pub fn verify(
+        &self,
+        proof: &R1CSProof,
+        commitments: &[StarkPoint],
+        pc_gens: &PedersenGens,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+    ) -> Result<(), R1CSError> {
+        // Keys may have been deserialized without going through the constructor
+        self.phases.check_weights(&self.weights)?;
+        if commitments.len() != self.phases.m {
+            return Err(R1CSError::GadgetError {
+                description: format!(
+                    "expected {} commitments, got {}",
+                    self.phases.m,
+                    commitments.len()
+                ),
+            });
+        }
+
+        let mut verifier = self.build_verifier(pc_gens, transcript, commitments)?;
+        for constraint in self.weights.to_constraints() {
+            verifier.constrain(constraint);
+        }
+
+        verifier.verify(proof, bp_gens)
+    }
+
+    /// Build a verifier with the commitments and the multipliers of each phase
+    /// allocated, but no constraints
+    fn build_verifier<'t, 'g>(
+        &self,
+        pc_gens: &'g PedersenGens,
+        transcript: &'t mut Transcript,
+        commitments: &[StarkPoint],
+    ) -> Result<Verifier<'t, 'g>, R1CSError> {
+        let mut verifier = Verifier::new(pc_gens, transcript);
+        for commitment in commitments.iter() {
+            verifier.commit(*commitment);
+        }
+
+        for _ in 0..self.phases.n1 {
+            verifier.allocate_multiplier(None)?;
+        }
+
+        let n2 = self.phases.n2;
+        if n2 > 0 {
+            verifier.specify_randomized_constraints(move |cs| {
+                for _ in 0..n2 {
+                    cs.allocate_multiplier(None)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(verifier)
+    }
+}
@@ -1,12 +1,14 @@
 #![allow(non_snake_case)]
 
-use itertools::Itertools;
 use merlin::HashChainTranscript as Transcript;
 use mpc_stark::algebra::scalar::Scalar;
 use mpc_stark::algebra::stark_curve::StarkPoint;
+use rand::{thread_rng, CryptoRng, RngCore};
 
+use super::prover::aggregated_G_factors;
 use super::{
-    CircuitWeights, ConstraintSystem, LinearCombination, R1CSProof, RandomizableConstraintSystem,
+    CircuitFingerprint, CircuitNamespaces, CircuitWeights, ConstraintSystem, FinalizedCircuit,
+    LinearCombination, Namespaces, PhaseSizes, R1CSProof, RandomizableConstraintSystem,
     RandomizedConstraintSystem, Variable,
 };
 
@@ -27,6 +29,10 @@ pub struct Verifier<'t, 'g> {
     transcript: &'t mut Transcript,
     pc_gens: &'g PedersenGens,
     constraints: Vec<LinearCombination>,
+    /// The namespaces of the constraints and variables
+    namespaces: Namespaces,
+    /// The fingerprint of the circuit the transcript is bound to, if any
+    fingerprint: Option<CircuitFingerprint>,
 
     /// Records the number of low-level variables allocated in the
     /// constraint system.
@@ -75,26 +81,11 @@ impl<'t, 'g> ConstraintSystem for Verifier<'t, 'g> {
     }
 
     fn get_weights(&self) -> CircuitWeights {
-        // Extract sparse-reduced weights from each constraint to construct the matrices
-        let (w_l, w_r, w_o, w_v, c) = self
-            // It's important that this iteration is in the correct order of the constraints,
-            // otherwise we'll write the wrong index for the given constant in a constraint
-            .constraints
-            .iter()
-            .enumerate()
-            .map(|(i, lc)| {
-                let (w_l_row, w_r_row, w_o_row, w_v_row, c_i) = lc.extract_weights();
-                (w_l_row, w_r_row, w_o_row, w_v_row, (i, c_i))
-            })
-            .multiunzip();
-
-        CircuitWeights {
-            w_l,
-            w_r,
-            w_o,
-            w_v,
-            c,
-        }
+        CircuitWeights::from_constraints(&self.constraints)
+    }
+
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.namespaces.recorded().clone()
     }
 
     fn multiply(
@@ -104,6 +95,7 @@ impl<'t, 'g> ConstraintSystem for Verifier<'t, 'g> {
     ) -> (Variable, Variable, Variable) {
         let var = self.num_vars;
         self.num_vars += 1;
+        self.namespaces.record_multiplier(var);
 
         // Create variables for l,r,o
         let l_var = Variable::MultiplierLeft(var);
@@ -120,11 +112,13 @@ impl<'t, 'g> ConstraintSystem for Verifier<'t, 'g> {
     }
 
     fn allocate(&mut self, _: Option<Scalar>) -> Result<Variable, R1CSError> {
+        self.namespaces.record_allocation();
         match self.pending_multiplier {
             None => {
                 let i = self.num_vars;
                 self.num_vars += 1;
                 self.pending_multiplier = Some(i);
+                self.namespaces.record_multiplier(i);
                 Ok(Variable::MultiplierLeft(i))
             }
             Some(i) => {
@@ -140,6 +134,7 @@ impl<'t, 'g> ConstraintSystem for Verifier<'t, 'g> {
     ) -> Result<(Variable, Variable, Variable), R1CSError> {
         let var = self.num_vars;
         self.num_vars += 1;
+        self.namespaces.record_multiplier(var);
 
         // Create variables for l,r,o
         let l_var = Variable::MultiplierLeft(var);
@@ -162,9 +157,25 @@ impl<'t, 'g> ConstraintSystem for Verifier<'t, 'g> {
         // TODO: check that the linear combinations are valid
         // (e.g. that variables are valid, that the linear combination
         // evals to 0 for prover, etc).
+        self.namespaces
+            .record_constraint(self.constraints.len(), None);
+        self.constraints.push(lc);
+    }
+
+    fn constrain_with_label(&mut self, lc: LinearCombination, label: &str) {
+        self.namespaces
+            .record_constraint(self.constraints.len(), Some(label));
         self.constraints.push(lc);
     }
 
+    fn push_namespace(&mut self, name: &str) {
+        self.namespaces.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespaces.pop_namespace()
+    }
+
     fn eval(&self, _: &LinearCombination) -> Scalar {
         // Dummy value, at verification time this method may be called by a circuit
         // reconstructing an implicit constraint from the underlying assignment.
@@ -181,7 +192,15 @@ impl<'t, 'g> RandomizableConstraintSystem for Verifier<'t, 'g> {
     where
         F: 'static + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
     {
-        self.deferred_constraints.push(Box::new(callback));
+        // Run the callback within the namespaces it was specified in
+        let scope = self.namespaces.scope();
+        self.deferred_constraints
+            .push(Box::new(move |cs: &mut RandomizingVerifier<'t, 'g>| {
+                cs.verifier.namespaces.enter(&scope);
+                let res = callback(cs);
+                cs.verifier.namespaces.exit(&scope);
+                res
+            }));
         Ok(())
     }
 }
@@ -203,6 +222,10 @@ impl<'t, 'g> ConstraintSystem for RandomizingVerifier<'t, 'g> {
         self.verifier.get_weights()
     }
 
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.verifier.get_namespaces()
+    }
+
     fn multiply(
         &mut self,
         left: LinearCombination,
@@ -230,6 +253,18 @@ impl<'t, 'g> ConstraintSystem for RandomizingVerifier<'t, 'g> {
         self.verifier.constrain(lc)
     }
 
+    fn constrain_with_label(&mut self, lc: LinearCombination, label: &str) {
+        self.verifier.constrain_with_label(lc, label)
+    }
+
+    fn push_namespace(&mut self, name: &str) {
+        self.verifier.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.verifier.pop_namespace()
+    }
+
     fn eval(&self, lc: &LinearCombination) -> Scalar {
         self.verifier.eval(lc)
     }
@@ -276,11 +311,35 @@ impl<'t, 'g> Verifier<'t, 'g> {
             num_vars: 0,
             V: Vec::new(),
             constraints: Vec::new(),
+            namespaces: Namespaces::default(),
+            fingerprint: None,
             deferred_constraints: Vec::new(),
             pending_multiplier: None,
         }
     }
 
+    /// Construct an empty constraint system bound to the circuit with the given
+    /// `fingerprint`.
+    ///
+    /// The fingerprint is absorbed into the transcript before any commitments, so only
+    /// proofs bound to the same circuit verify. Verification fails with
+    /// [`R1CSError::CircuitFingerprintMismatch`] if the circuit specified does not match
+    /// the fingerprint, or if the proof carries a different fingerprint, before any
+    /// group operations are performed.
+    pub fn new_with_fingerprint(
+        pc_gens: &'g PedersenGens,
+        transcript: &'t mut Transcript,
+        fingerprint: CircuitFingerprint,
+    ) -> Self {
+        let mut verifier = Self::new(pc_gens, transcript);
+        verifier
+            .transcript
+            .r1cs_circuit_domain_sep(fingerprint.as_bytes());
+        verifier.fingerprint = Some(fingerprint);
+
+        verifier
+    }
+
     /// Creates commitment to a high-level variable and adds it to the transcript.
     ///
     /// # Inputs
@@ -297,6 +356,7 @@ impl<'t, 'g> Verifier<'t, 'g> {
     /// and a [`Variable`] corresponding to it, which can be used to form constraints.
     pub fn commit(&mut self, commitment: StarkPoint) -> Variable {
         let i = self.V.len();
+        self.namespaces.record_commitment(i);
         self.V.push(commitment);
 
         // Add the commitment to the transcript.
@@ -323,6 +383,21 @@ impl<'t, 'g> Verifier<'t, 'g> {
     pub fn flattened_constraints(
         &mut self,
         z: &Scalar,
+    ) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Scalar) {
+        let mut exp_z = *z;
+        self.flattened_constraints_from(z, &mut exp_z)
+    }
+
+    /// Flatten the constraints as in `flattened_constraints`, weighting the
+    /// first constraint by `exp_z` rather than by `z`.
+    ///
+    /// On return, `exp_z` holds the weight of the next constraint, so that the
+    /// constraints of several constraint systems can be flattened with distinct
+    /// powers of `z`.
+    fn flattened_constraints_from(
+        &self,
+        z: &Scalar,
+        exp_z: &mut Scalar,
     ) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Scalar) {
         let n = self.num_vars;
         let m = self.V.len();
@@ -333,29 +408,28 @@ impl<'t, 'g> Verifier<'t, 'g> {
         let mut wV = vec![Scalar::zero(); m];
         let mut wc = Scalar::zero();
 
-        let mut exp_z = *z;
         for lc in self.constraints.iter() {
             for (var, coeff) in &lc.terms {
                 match var {
                     Variable::MultiplierLeft(i) => {
-                        wL[*i] += exp_z * coeff;
+                        wL[*i] += *exp_z * coeff;
                     }
                     Variable::MultiplierRight(i) => {
-                        wR[*i] += exp_z * coeff;
+                        wR[*i] += *exp_z * coeff;
                     }
                     Variable::MultiplierOutput(i) => {
-                        wO[*i] += exp_z * coeff;
+                        wO[*i] += *exp_z * coeff;
                     }
                     Variable::Committed(i) => {
-                        wV[*i] -= exp_z * coeff;
+                        wV[*i] -= *exp_z * coeff;
                     }
                     Variable::One() => {
-                        wc -= exp_z * coeff;
+                        wc -= *exp_z * coeff;
                     }
                     Variable::Zero() => {}
                 }
             }
-            exp_z *= *z;
+            *exp_z *= *z;
         }
 
         (wL, wR, wO, wV, wc)
@@ -366,10 +440,13 @@ impl<'t, 'g> Verifier<'t, 'g> {
     fn create_randomized_constraints(mut self) -> Result<Self, R1CSError> {
         // Clear the pending multiplier (if any) because it was committed into A_L/A_R/S.
         self.pending_multiplier = None;
+        self.namespaces.begin_randomized_phase();
 
-        if self.deferred_constraints.is_empty() {
+        let n1 = self.num_vars;
+        let q1 = self.constraints.len();
+        let verifier = if self.deferred_constraints.is_empty() {
             self.transcript.r1cs_1phase_domain_sep();
-            Ok(self)
+            self
         } else {
             self.transcript.r1cs_2phase_domain_sep();
             // Note: the wrapper could've used &mut instead of ownership,
@@ -380,17 +457,364 @@ impl<'t, 'g> Verifier<'t, 'g> {
             for callback in callbacks.drain(..) {
                 callback(&mut wrapped_self)?;
             }
-            Ok(wrapped_self.verifier)
+            wrapped_self.verifier
+        };
+
+        verifier.check_fingerprint(n1, q1)?;
+        Ok(verifier)
+    }
+
+    /// Compute the fingerprint of the circuit, given the number of multipliers and
+    /// constraints allocated in its first phase
+    fn circuit_fingerprint(&self, n1: usize, q1: usize) -> CircuitFingerprint {
+        let phases = PhaseSizes {
+            n1,
+            n2: self.num_vars - n1,
+            m: self.V.len(),
+        };
+        let weights = CircuitWeights::from_constraints(&self.constraints[..q1]);
+
+        CircuitFingerprint::new(&weights, &phases)
+    }
+
+    /// Check that the circuit matches the fingerprint the transcript is bound to, if any
+    fn check_fingerprint(&self, n1: usize, q1: usize) -> Result<(), R1CSError> {
+        match self.fingerprint {
+            Some(fingerprint) if fingerprint != self.circuit_fingerprint(n1, q1) => {
+                Err(R1CSError::CircuitFingerprintMismatch)
+            }
+            _ => Ok(()),
         }
     }
 
+    /// Consume the verifier, running its randomized constraints, and return the finished
+    /// circuit, from which its fingerprint, DOT graph and profile are derived
+    ///
+    /// Without the `namespaces` feature, the circuit records no namespaces beyond the
+    /// labels of explicitly labeled constraints, and its profile only has a root scope
+    ///
+    /// The randomized constraints draw challenges from the transcript, so the transcript
+    /// cannot be used to verify a proof afterwards
+    pub fn finalize(self) -> Result<FinalizedCircuit, R1CSError> {
+        let n1 = self.num_vars;
+        let q1 = self.constraints.len();
+        let verifier = self.create_randomized_constraints()?;
+
+        Ok(FinalizedCircuit {
+            weights: verifier.get_weights(),
+            phase_one_constraints: q1,
+            phases: PhaseSizes {
+                n1,
+                n2: verifier.num_vars - n1,
+                m: verifier.V.len(),
+            },
+            namespaces: verifier.get_namespaces(),
+            profile: verifier.namespaces.profile().clone(),
+        })
+    }
+
     /// Consume this `VerifierCS` and attempt to verify the supplied `proof`.
     /// The `pc_gens` and `bp_gens` are generators for Pedersen commitments and
     /// Bulletproofs vector commitments, respectively.  The
     /// [`BulletproofGens`] should have `gens_capacity` greater than
     /// the number of multiplication constraints that will eventually
     /// be added into the constraint system.
-    pub fn verify(mut self, proof: &R1CSProof, bp_gens: &BulletproofGens) -> Result<(), R1CSError> {
+    pub fn verify(self, proof: &R1CSProof, bp_gens: &BulletproofGens) -> Result<(), R1CSError> {
+        let terms = self.verification_terms(proof, bp_gens)?;
+        if !terms.is_satisfied(bp_gens) {
+            return Err(R1CSError::VerificationError);
+        }
+
+        Ok(())
+    }
+
+    /// Verify a batch of proofs, each against its own `Verifier`, using a single
+    /// multiscalar multiplication.
+    ///
+    /// Each proof's verification equation is weighted by an independent random
+    /// scalar and the equations are summed, so that the scalars applied to the
+    /// shared `G` and `H` generators are merged across the batch.  If the batch
+    /// fails to verify, each proof is checked individually and
+    /// [`R1CSError::BatchVerificationError`] is returned with the indices of all
+    /// proofs that failed.
+    ///
+    /// Returns [`R1CSError::InvalidAggregation`] if the batch is empty.
+    pub fn verify_batch(
+        batch: Vec<(Self, &R1CSProof)>,
+        bp_gens: &BulletproofGens,
+    ) -> Result<(), R1CSError> {
+        Self::verify_batch_with_rng(batch, bp_gens, &mut thread_rng())
+    }
+
+    /// Verify a batch of proofs using the given `rng` to sample the weights of
+    /// each proof's verification equation.
+    ///
+    /// See [`Verifier::verify_batch`] for details.
+    pub fn verify_batch_with_rng<T: RngCore + CryptoRng>(
+        batch: Vec<(Self, &R1CSProof)>,
+        bp_gens: &BulletproofGens,
+        rng: &mut T,
+    ) -> Result<(), R1CSError> {
+        if batch.is_empty() {
+            return Err(R1CSError::InvalidAggregation);
+        }
+
+        // Replay each proof's transcript, a proof that cannot be replayed
+        // (e.g. one that contains an identity commitment) is marked as bad
+        let mut bad_proofs = Vec::new();
+        let mut batch_terms = Vec::with_capacity(batch.len());
+        for (i, (verifier, proof)) in batch.into_iter().enumerate() {
+            match verifier.verification_terms(proof, bp_gens) {
+                Ok(terms) => batch_terms.push((i, terms)),
+                Err(R1CSError::VerificationError) => bad_proofs.push(i),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if bad_proofs.is_empty()
+            && VerificationTerms::batch_is_satisfied(&batch_terms, bp_gens, rng)
+        {
+            return Ok(());
+        }
+
+        // Fall back to checking each proof individually to find the bad proofs
+        bad_proofs.extend(
+            batch_terms
+                .iter()
+                .filter(|(_, terms)| !terms.is_satisfied(bp_gens))
+                .map(|(i, _)| *i),
+        );
+        bad_proofs.sort_unstable();
+
+        if bad_proofs.is_empty() {
+            Err(R1CSError::VerificationError)
+        } else {
+            Err(R1CSError::BatchVerificationError { bad_proofs })
+        }
+    }
+
+    /// Verify an aggregated proof of a set of constraint systems, one for each
+    /// of `k` circuit instances, as created by
+    /// [`Prover::prove_aggregated`](super::Prover::prove_aggregated).
+    ///
+    /// The verifiers must be given in the same order as the provers, each built
+    /// against a transcript in the same state as its prover's transcript, and
+    /// `transcript` must be in the same state as the prover's aggregated transcript.
+    pub fn verify_aggregated(
+        mut verifiers: Vec<Self>,
+        proof: &R1CSProof,
+        transcript: &mut Transcript,
+        bp_gens: &BulletproofGens,
+    ) -> Result<(), R1CSError> {
+        use crate::inner_product_proof::inner_product;
+        use crate::util;
+        use std::iter;
+
+        if verifiers.is_empty() {
+            return Err(R1CSError::InvalidAggregation);
+        }
+
+        let k = verifiers.len();
+        let padded_k = k.next_power_of_two();
+        if bp_gens.party_capacity < padded_k {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let pc_gens = verifiers[0].pc_gens;
+        transcript.r1cs_aggregated_domain_sep(k as u64);
+
+        // Bind each instance's statement to the aggregated transcript
+        for verifier in verifiers.iter_mut() {
+            verifier
+                .transcript
+                .append_u64(b"m", verifier.V.len() as u64);
+            let instance_challenge = verifier.transcript.challenge_scalar(b"instance");
+            transcript.append_scalar(b"instance", &instance_challenge);
+        }
+
+        let n1: Vec<usize> = verifiers.iter().map(|verifier| verifier.num_vars).collect();
+        transcript.validate_and_append_point(b"A_I1", &proof.A_I1)?;
+        transcript.validate_and_append_point(b"A_O1", &proof.A_O1)?;
+        transcript.validate_and_append_point(b"S1", &proof.S1)?;
+
+        // Bind the randomized constraints of each instance to the first-phase
+        // commitments, then process the remaining constraints
+        let verifiers = verifiers
+            .into_iter()
+            .map(|mut verifier| {
+                verifier.transcript.append_point(b"A_I1", &proof.A_I1);
+                verifier.transcript.append_point(b"A_O1", &proof.A_O1);
+                verifier.transcript.append_point(b"S1", &proof.S1);
+                verifier.create_randomized_constraints()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Every instance is padded to the size of the largest instance, rounded up
+        // to the next power of two
+        let n: Vec<usize> = verifiers.iter().map(|verifier| verifier.num_vars).collect();
+        let padded_n = n.iter().copied().max().unwrap_or(0).next_power_of_two();
+        let padded_size = padded_k * padded_n;
+
+        if bp_gens.gens_capacity < padded_n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        // These points are the identity in the 1-phase un-randomized case.
+        transcript.append_point(b"A_I2", &proof.A_I2);
+        transcript.append_point(b"A_O2", &proof.A_O2);
+        transcript.append_point(b"S2", &proof.S2);
+
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+
+        transcript.validate_and_append_point(b"T_1", &proof.T_1)?;
+        transcript.validate_and_append_point(b"T_3", &proof.T_3)?;
+        transcript.validate_and_append_point(b"T_4", &proof.T_4)?;
+        transcript.validate_and_append_point(b"T_5", &proof.T_5)?;
+        transcript.validate_and_append_point(b"T_6", &proof.T_6)?;
+
+        let u = transcript.challenge_scalar(b"u");
+        let x = transcript.challenge_scalar(b"x");
+
+        transcript.append_scalar(b"t_x", &proof.t_x);
+        transcript.append_scalar(b"t_x_blinding", &proof.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &proof.e_blinding);
+
+        let w = transcript.challenge_scalar(b"w");
+
+        // Lay out the flattened constraints of each instance over its section of
+        // the generators, the padding multipliers have zero weights.  Each
+        // instance's constraints are weighted by the powers of `z` following those
+        // of the previous instance's constraints
+        let mut wL = vec![Scalar::zero(); padded_size];
+        let mut wR = vec![Scalar::zero(); padded_size];
+        let mut wO = vec![Scalar::zero(); padded_size];
+        let mut wV = Vec::new();
+        let mut wc = Scalar::zero();
+
+        let mut exp_z = z;
+        for (j, verifier) in verifiers.iter().enumerate() {
+            let (wL_j, wR_j, wO_j, wV_j, wc_j) =
+                verifier.flattened_constraints_from(&z, &mut exp_z);
+
+            let offset = j * padded_n;
+            wL[offset..offset + wL_j.len()].copy_from_slice(&wL_j);
+            wR[offset..offset + wR_j.len()].copy_from_slice(&wR_j);
+            wO[offset..offset + wO_j.len()].copy_from_slice(&wO_j);
+            wV.extend(wV_j);
+            wc += wc_j;
+        }
+
+        // Get IPP variables
+        let (u_sq, u_inv_sq, s) = proof
+            .ipp_proof
+            .verification_scalars(padded_size, transcript)
+            .map_err(|_| R1CSError::VerificationError)?;
+
+        let a = proof.ipp_proof.a;
+        let b = proof.ipp_proof.b;
+
+        let y_inv = y.inverse();
+        let y_inv_vec = util::exp_iter(y_inv)
+            .take(padded_size)
+            .collect::<Vec<Scalar>>();
+        let yneg_wR = wR
+            .into_iter()
+            .zip(y_inv_vec.iter())
+            .map(|(wRi, exp_y_inv)| wRi * exp_y_inv)
+            .collect::<Vec<Scalar>>();
+
+        let delta = inner_product(&yneg_wR, &wL);
+
+        let u_for_g = aggregated_G_factors(&n1, padded_k, padded_n, u);
+        let u_for_h = u_for_g.clone();
+
+        // define parameters for P check
+        let g_scalars = yneg_wR
+            .iter()
+            .zip(u_for_g.into_iter())
+            .zip(s.iter())
+            .map(|((yneg_wRi, u_or_1), s_i)| u_or_1 * (x * yneg_wRi - a * s_i));
+
+        let h_scalars = y_inv_vec
+            .iter()
+            .zip(u_for_h.into_iter())
+            .zip(s.iter().rev())
+            .zip(wL.into_iter())
+            .zip(wO.into_iter())
+            .map(|((((y_inv_i, u_or_1), s_i_inv), wLi), wOi)| {
+                u_or_1 * (y_inv_i * (x * wLi + wOi - b * s_i_inv) - Scalar::one())
+            });
+
+        let r = transcript.challenge_scalar(b"r");
+
+        let xx = x * x;
+        let rxx = r * xx;
+        let xxx = x * xx;
+
+        // group the T_scalars and T_points together
+        let T_scalars = [r * x, rxx * x, rxx * xx, rxx * xxx, rxx * xx * xx];
+        let T_points = [proof.T_1, proof.T_3, proof.T_4, proof.T_5, proof.T_6];
+
+        let mega_check = StarkPoint::msm_iter(
+            iter::once(x) // A_I1
+                .chain(iter::once(xx)) // A_O1
+                .chain(iter::once(xxx)) // S1
+                .chain(iter::once(u * x)) // A_I2
+                .chain(iter::once(u * xx)) // A_O2
+                .chain(iter::once(u * xxx)) // S2
+                .chain(wV.iter().map(|wVi| wVi * rxx)) // V
+                .chain(T_scalars.iter().cloned()) // T_points
+                .chain(iter::once(
+                    w * (proof.t_x - a * b) + r * (xx * (wc + delta) - proof.t_x),
+                )) // B
+                .chain(iter::once(-proof.e_blinding - r * proof.t_x_blinding)) // B_blinding
+                .chain(u_sq.iter().cloned()) // ipp_proof.L_vec
+                .chain(u_inv_sq.iter().cloned()) // ipp_proof.R_vec
+                .chain(g_scalars)
+                .chain(h_scalars),
+            iter::once(proof.A_I1)
+                .chain(iter::once(proof.A_O1))
+                .chain(iter::once(proof.S1))
+                .chain(iter::once(proof.A_I2))
+                .chain(iter::once(proof.A_O2))
+                .chain(iter::once(proof.S2))
+                .chain(
+                    verifiers
+                        .iter()
+                        .flat_map(|verifier| verifier.V.iter().copied()),
+                )
+                .chain(T_points.into_iter())
+                .chain(iter::once(pc_gens.B))
+                .chain(iter::once(pc_gens.B_blinding))
+                .chain(proof.ipp_proof.L_vec.iter().copied())
+                .chain(proof.ipp_proof.R_vec.iter().copied())
+                .chain(bp_gens.G(padded_n, padded_k).copied())
+                .chain(bp_gens.H(padded_n, padded_k).copied()),
+        );
+
+        if !mega_check.is_identity() {
+            return Err(R1CSError::VerificationError);
+        }
+
+        Ok(())
+    }
+
+    /// Consume this `VerifierCS`, replay the transcript of the supplied `proof`
+    /// and compute the terms of the verification equation.
+    ///
+    /// The proof is valid if the resulting terms form a multiscalar multiplication
+    /// that evaluates to the identity.
+    fn verification_terms(
+        mut self,
+        proof: &R1CSProof,
+        bp_gens: &BulletproofGens,
+    ) -> Result<VerificationTerms, R1CSError> {
+        // Reject a proof for another circuit before replaying its transcript
+        if proof.fingerprint != self.fingerprint {
+            return Err(R1CSError::CircuitFingerprintMismatch);
+        }
+
         // Commit a length _suffix_ for the number of high-level variables.
         // We cannot do this in advance because user can commit variables one-by-one,
         // but this suffix provides safe disambiguation because each variable
@@ -421,8 +845,6 @@ impl<'t, 'g> Verifier<'t, 'g> {
         if bp_gens.gens_capacity < padded_n {
             return Err(R1CSError::InvalidGeneratorsLength);
         }
-        // We are performing a single-party circuit proof, so party index is 0.
-        let gens = bp_gens.share(0);
 
         // These points are the identity in the 1-phase un-randomized case.
         self.transcript.append_point(b"A_I2", &proof.A_I2);
@@ -513,43 +935,131 @@ impl<'t, 'g> Verifier<'t, 'g> {
         let T_scalars = [r * x, rxx * x, rxx * xx, rxx * xxx, rxx * xx * xx];
         let T_points = [proof.T_1, proof.T_3, proof.T_4, proof.T_5, proof.T_6];
 
+        let dynamic_scalars = iter::once(x) // A_I1
+            .chain(iter::once(xx)) // A_O1
+            .chain(iter::once(xxx)) // S1
+            .chain(iter::once(u * x)) // A_I2
+            .chain(iter::once(u * xx)) // A_O2
+            .chain(iter::once(u * xxx)) // S2
+            .chain(wV.iter().map(|wVi| wVi * rxx)) // V
+            .chain(T_scalars.iter().cloned()) // T_points
+            .chain(iter::once(
+                w * (proof.t_x - a * b) + r * (xx * (wc + delta) - proof.t_x),
+            )) // B
+            .chain(iter::once(-proof.e_blinding - r * proof.t_x_blinding)) // B_blinding
+            .chain(u_sq.iter().cloned()) // ipp_proof.L_vec
+            .chain(u_inv_sq.iter().cloned()) // ipp_proof.R_vec
+            .collect();
+        let dynamic_points = iter::once(proof.A_I1)
+            .chain(iter::once(proof.A_O1))
+            .chain(iter::once(proof.S1))
+            .chain(iter::once(proof.A_I2))
+            .chain(iter::once(proof.A_O2))
+            .chain(iter::once(proof.S2))
+            .chain(self.V.into_iter())
+            .chain(T_points.into_iter())
+            .chain(iter::once(self.pc_gens.B))
+            .chain(iter::once(self.pc_gens.B_blinding))
+            .chain(proof.ipp_proof.L_vec.iter().copied())
+            .chain(proof.ipp_proof.R_vec.iter().copied())
+            .collect();
+
+        Ok(VerificationTerms {
+            dynamic_scalars,
+            dynamic_points,
+            g_scalars: g_scalars.collect(),
+            h_scalars: h_scalars.collect(),
+        })
+    }
+}
+
+/// The terms of a proof's verification equation, a multiscalar multiplication
+/// that evaluates to the identity for a valid proof.
+///
+/// The terms are split into those specific to a proof and the scalars applied
+/// to the `G` and `H` generators, which are shared between the proofs of a batch.
+struct VerificationTerms {
+    /// The scalars applied to the proof's commitments and the Pedersen generators
+    dynamic_scalars: Vec<Scalar>,
+    /// The proof's commitments and the Pedersen generators
+    dynamic_points: Vec<StarkPoint>,
+    /// The scalars applied to the `G` generators
+    g_scalars: Vec<Scalar>,
+    /// The scalars applied to the `H` generators
+    h_scalars: Vec<Scalar>,
+}
+
+impl VerificationTerms {
+    /// Check that the verification equation of a single proof holds.
+    fn is_satisfied(&self, bp_gens: &BulletproofGens) -> bool {
+        // We are performing a single-party circuit proof, so party index is 0.
+        let gens = bp_gens.share(0);
+        let padded_n = self.g_scalars.len();
+
         let mega_check = StarkPoint::msm_iter(
-            iter::once(x) // A_I1
-                .chain(iter::once(xx)) // A_O1
-                .chain(iter::once(xxx)) // S1
-                .chain(iter::once(u * x)) // A_I2
-                .chain(iter::once(u * xx)) // A_O2
-                .chain(iter::once(u * xxx)) // S2
-                .chain(wV.iter().map(|wVi| wVi * rxx)) // V
-                .chain(T_scalars.iter().cloned()) // T_points
-                .chain(iter::once(
-                    w * (proof.t_x - a * b) + r * (xx * (wc + delta) - proof.t_x),
-                )) // B
-                .chain(iter::once(-proof.e_blinding - r * proof.t_x_blinding)) // B_blinding
-                .chain(g_scalars) // G
-                .chain(h_scalars) // H
-                .chain(u_sq.iter().cloned()) // ipp_proof.L_vec
-                .chain(u_inv_sq.iter().cloned()), // ipp_proof.R_vec
-            iter::once(proof.A_I1)
-                .chain(iter::once(proof.A_O1))
-                .chain(iter::once(proof.S1))
-                .chain(iter::once(proof.A_I2))
-                .chain(iter::once(proof.A_O2))
-                .chain(iter::once(proof.S2))
-                .chain(self.V.into_iter())
-                .chain(T_points.into_iter())
-                .chain(iter::once(self.pc_gens.B))
-                .chain(iter::once(self.pc_gens.B_blinding))
-                .chain(gens.G(padded_n).copied())
-                .chain(gens.H(padded_n).copied())
-                .chain(proof.ipp_proof.L_vec.iter().copied())
-                .chain(proof.ipp_proof.R_vec.iter().copied()),
+            self.dynamic_scalars
+                .iter()
+                .chain(self.g_scalars.iter())
+                .chain(self.h_scalars.iter())
+                .copied(),
+            self.dynamic_points
+                .iter()
+                .chain(gens.G(padded_n))
+                .chain(gens.H(padded_n))
+                .copied(),
         );
 
-        if !mega_check.is_identity() {
-            return Err(R1CSError::VerificationError);
+        mega_check.is_identity()
+    }
+
+    /// Check that the verification equations of a batch of proofs hold by taking
+    /// a random linear combination of the equations.
+    ///
+    /// The scalars applied to the `G` and `H` generators are summed across the
+    /// batch, so each generator appears once in the multiscalar multiplication.
+    fn batch_is_satisfied<T: RngCore + CryptoRng>(
+        batch: &[(usize, VerificationTerms)],
+        bp_gens: &BulletproofGens,
+        rng: &mut T,
+    ) -> bool {
+        // We are performing single-party circuit proofs, so party index is 0.
+        let gens = bp_gens.share(0);
+        let max_padded_n = batch
+            .iter()
+            .map(|(_, terms)| terms.g_scalars.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut g_scalars = vec![Scalar::zero(); max_padded_n];
+        let mut h_scalars = vec![Scalar::zero(); max_padded_n];
+        let mut dynamic_scalars = Vec::new();
+        let mut dynamic_points = Vec::new();
+
+        for (_, terms) in batch.iter() {
+            let weight = Scalar::random(rng);
+
+            for (acc, g_i) in g_scalars.iter_mut().zip(terms.g_scalars.iter()) {
+                *acc += weight * g_i;
+            }
+            for (acc, h_i) in h_scalars.iter_mut().zip(terms.h_scalars.iter()) {
+                *acc += weight * h_i;
+            }
+
+            dynamic_scalars.extend(terms.dynamic_scalars.iter().map(|s| weight * s));
+            dynamic_points.extend(terms.dynamic_points.iter().copied());
         }
 
-        Ok(())
+        let mega_check = StarkPoint::msm_iter(
+            dynamic_scalars
+                .into_iter()
+                .chain(g_scalars.into_iter())
+                .chain(h_scalars.into_iter()),
+            dynamic_points
+                .into_iter()
+                .chain(gens.G(max_padded_n).copied())
+                .chain(gens.H(max_padded_n).copied()),
+        );
+
+        mega_check.is_identity()
     }
 }
@@ -0,0 +1,127 @@
+//! Definition of an explicit witness, used to prove a circuit given as weights
+//! without executing gadget code.
+
+#![allow(non_snake_case)]
+
+use merlin::HashChainTranscript as Transcript;
+use mpc_stark::algebra::scalar::Scalar;
+use mpc_stark::algebra::stark_curve::StarkPoint;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    CircuitWeights, ConstraintSystem, PhaseSizes, Prover, R1CSProof, RandomizableConstraintSystem,
+};
+use crate::errors::R1CSError;
+use crate::generators::{BulletproofGens, PedersenGens};
+
+/// An assignment to the variables of a circuit, generated outside of the
+/// constraint system.
+///
+/// The multiplier assignments are ordered by the multipliers' indices, with
+/// the multipliers of the randomized phase following those of the first phase.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Witness {
+    /// Assignments to the "left" of multiplication gates
+    pub a_L: Vec<Scalar>,
+    /// Assignments to the "right" of multiplication gates
+    pub a_R: Vec<Scalar>,
+    /// Assignments to the "output" of multiplication gates
+    pub a_O: Vec<Scalar>,
+    /// High-level witness data (value openings to V commitments)
+    pub v: Vec<Scalar>,
+    /// High-level witness data (blinding openings to V commitments)
+    pub v_blinding: Vec<Scalar>,
+}
+
+impl Witness {
+    /// Check that the witness assigns every variable of a circuit with the
+    /// given phase sizes, and that each multiplier's output is the product of
+    /// its inputs.
+    fn check(&self, phases: &PhaseSizes) -> Result<(), R1CSError> {
+        let n = phases.n1 + phases.n2;
+        if self.a_L.len() != n
+            || self.a_R.len() != n
+            || self.a_O.len() != n
+            || self.v.len() != phases.m
+            || self.v_blinding.len() != phases.m
+        {
+            return Err(R1CSError::GadgetError {
+                description: "witness does not match the circuit's phase sizes".to_string(),
+            });
+        }
+
+        let products_hold = self
+            .a_L
+            .iter()
+            .zip(self.a_R.iter())
+            .zip(self.a_O.iter())
+            .all(|((l, r), o)| *l * *r == *o);
+        if !products_hold {
+            return Err(R1CSError::GadgetError {
+                description: "witness multiplier outputs are not the products of their inputs"
+                    .to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'t, 'g> Prover<'t, 'g> {
+    /// Prove a circuit given by its `weights` and phase sizes, using an explicit
+    /// `witness` in place of gadget code.
+    ///
+    /// This mirrors [`VerificationKey::verify`](super::VerificationKey::verify):
+    /// the weights are static, so randomized constraints whose weights depend
+    /// on challenge scalars cannot be represented.
+    ///
+    /// Returns the proof along with the commitments to the high-level variables.
+    pub fn prove_with_weights(
+        pc_gens: &'g PedersenGens,
+        transcript: &'t mut Transcript,
+        weights: &CircuitWeights,
+        phases: PhaseSizes,
+        witness: &Witness,
+        bp_gens: &BulletproofGens,
+    ) -> Result<(R1CSProof, Vec<StarkPoint>), R1CSError> {
+        phases.check_weights(weights)?;
+        witness.check(&phases)?;
+
+        let mut prover = Prover::new(pc_gens, transcript);
+        let commitments: Vec<StarkPoint> = witness
+            .v
+            .iter()
+            .zip(witness.v_blinding.iter())
+            .map(|(v, v_blinding)| prover.commit(*v, *v_blinding).0)
+            .collect();
+
+        // Allocate the multipliers of each phase with their assignments
+        let n1 = phases.n1;
+        for (l, r) in witness.a_L.iter().zip(witness.a_R.iter()).take(n1) {
+            prover.allocate_multiplier(Some((*l, *r)))?;
+        }
+
+        if phases.n2 > 0 {
+            let phase2_inputs: Vec<(Scalar, Scalar)> = witness
+                .a_L
+                .iter()
+                .copied()
+                .zip(witness.a_R.iter().copied())
+                .skip(n1)
+                .collect();
+            prover.specify_randomized_constraints(move |cs| {
+                for (l, r) in phase2_inputs.iter() {
+                    cs.allocate_multiplier(Some((*l, *r)))?;
+                }
+                Ok(())
+            })?;
+        }
+
+        for constraint in weights.to_constraints() {
+            prover.constrain(constraint);
+        }
+
+        let proof = prover.prove(bp_gens)?;
+        Ok((proof, commitments))
+    }
+}
@@ -0,0 +1,376 @@
+//! Import of circuits compiled by circom
+//!
+//! Circom emits a circuit as an `.r1cs` file, in which each constraint takes the form
+//! `A * B = C` for linear combinations `A`, `B` and `C` over the circuit's wires, and
+//! emits a witness as a `.wtns` file assigning a value to each wire. Wire 0 is the
+//! constant one, followed by the public outputs, the public inputs, and then the
+//! private inputs and intermediate signals.
+//!
+//! A [`CircomCircuit`] is applied to any [`GenericConstraintSystem`], so the same file
+//! drives the prover, the verifier and the collaborative prover.
+//!
+//! Only files over the Stark scalar field are supported; files over any other field are
+//! rejected. The circom compiler's `--prime` option does not offer the Stark field, so
+//! such files must be produced by other tooling that emits the `.r1cs` and `.wtns`
+//! formats over it.
+
+use mpc_stark::algebra::scalar::{Scalar, SCALAR_BYTES};
+use num_bigint::BigUint;
+
+use super::GenericConstraintSystem;
+use crate::errors::R1CSError;
+use crate::util;
+
+/// The section of an `.r1cs` file holding the header
+const R1CS_HEADER_SECTION: u32 = 1;
+/// The section of an `.r1cs` file holding the constraints
+const R1CS_CONSTRAINTS_SECTION: u32 = 2;
+/// The section of a `.wtns` file holding the header
+const WTNS_HEADER_SECTION: u32 = 1;
+/// The section of a `.wtns` file holding the witness values
+const WTNS_VALUES_SECTION: u32 = 2;
+
+/// A linear combination of wires, as pairs of a wire index and a coefficient
+type WireCombination = Vec<(usize, Scalar)>;
+
+/// A constraint of a circom circuit, enforcing `a * b = c`
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CircomConstraint {
+    a: WireCombination,
+    b: WireCombination,
+    c: WireCombination,
+}
+
+/// A circuit loaded from a circom `.r1cs` file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircomCircuit {
+    /// The number of wires, including the constant wire
+    num_wires: usize,
+    /// The number of public signals, outputs followed by inputs
+    num_public: usize,
+    /// The constraints of the circuit
+    constraints: Vec<CircomConstraint>,
+}
+
+/// A witness loaded from a circom `.wtns` file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircomWitness {
+    /// The value of each wire, beginning with the constant wire
+    values: Vec<Scalar>,
+}
+
+impl CircomCircuit {
+    /// Parse a circuit from the contents of an `.r1cs` file
+    pub fn from_r1cs_bytes(bytes: &[u8]) -> Result<Self, R1CSError> {
+        let sections = read_sections(bytes, b"r1cs", 1)?;
+
+        let mut header = Reader::new(find_section(&sections, R1CS_HEADER_SECTION)?);
+        read_field(&mut header)?;
+        let num_wires = header.read_u32()? as usize;
+        let num_public_outputs = header.read_u32()? as usize;
+        let num_public_inputs = header.read_u32()? as usize;
+        let _num_private_inputs = header.read_u32()?;
+        let _num_labels = header.read_u64()?;
+        let num_constraints = header.read_u32()? as usize;
+
+        let num_public = num_public_outputs + num_public_inputs;
+        if num_wires == 0 || num_public >= num_wires {
+            return Err(R1CSError::FormatError);
+        }
+
+        let mut reader = Reader::new(find_section(&sections, R1CS_CONSTRAINTS_SECTION)?);
+        let constraints = (0..num_constraints)
+            .map(|_| {
+                Ok(CircomConstraint {
+                    a: reader.read_combination(num_wires)?,
+                    b: reader.read_combination(num_wires)?,
+                    c: reader.read_combination(num_wires)?,
+                })
+            })
+            .collect::<Result<Vec<_>, R1CSError>>()?;
+        reader.finish()?;
+
+        Ok(Self {
+            num_wires,
+            num_public,
+            constraints,
+        })
+    }
+
+    /// The number of wires in the circuit, including the constant wire
+    pub fn num_wires(&self) -> usize {
+        self.num_wires
+    }
+
+    /// The number of public signals, i.e. public outputs and inputs
+    pub fn num_public(&self) -> usize {
+        self.num_public
+    }
+
+    /// The number of constraints in the circuit
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// The values of the public signals assigned by a witness, outputs followed by inputs
+    pub fn public_signals<'a>(
+        &self,
+        witness: &'a CircomWitness,
+    ) -> Result<&'a [Scalar], R1CSError> {
+        self.check_witness(witness)?;
+        Ok(&witness.values[1..=self.num_public])
+    }
+
+    /// The values of the private signals assigned by a witness, in wire order
+    pub fn private_signals<'a>(
+        &self,
+        witness: &'a CircomWitness,
+    ) -> Result<&'a [Scalar], R1CSError> {
+        self.check_witness(witness)?;
+        Ok(&witness.values[self.num_public + 1..])
+    }
+
+    /// Specify the circuit's constraints in a constraint system
+    ///
+    /// Each public signal is committed with [`GenericConstraintSystem::commit_public`],
+    /// so `public_signals` must be given by the verifier as well as the prover. Each
+    /// private signal is allocated with [`GenericConstraintSystem::allocate`], assigned
+    /// from `private_signals` if given; the verifier passes `None`. A collaborative
+    /// prover passes the private signals shared by the party holding the witness, e.g.
+    /// with `MpcFabric::batch_share_scalar`.
+    ///
+    /// Each constraint `a * b = c` is specified with a multiplication gate, unless `a`
+    /// or `b` is empty, in which case `c = 0` is constrained directly.
+    ///
+    /// Returns the variables of the wires, excluding the constant wire
+    pub fn synthesize<CS: GenericConstraintSystem>(
+        &self,
+        cs: &mut CS,
+        public_signals: &[Scalar],
+        private_signals: Option<Vec<CS::Value>>,
+    ) -> Result<Vec<CS::Variable>, R1CSError> {
+        let num_private = self.num_wires - self.num_public - 1;
+        let private_signals_match = match private_signals.as_ref() {
+            Some(values) => values.len() == num_private,
+            None => true,
+        };
+        if public_signals.len() != self.num_public || !private_signals_match {
+            return Err(R1CSError::GadgetError {
+                description: "circom signals do not match the circuit's wires".to_string(),
+            });
+        }
+
+        let mut wires: Vec<CS::Variable> = public_signals
+            .iter()
+            .map(|value| cs.commit_public(*value))
+            .collect();
+        match private_signals {
+            Some(values) => {
+                for value in values {
+                    wires.push(cs.allocate(Some(value))?);
+                }
+            }
+            None => {
+                for _ in 0..num_private {
+                    wires.push(cs.allocate(None)?);
+                }
+            }
+        }
+
+        let combination = |cs: &CS, terms: &WireCombination| {
+            terms.iter().fold(
+                cs.constant(Scalar::zero()),
+                |lc, (wire, coeff)| match wire {
+                    0 => lc + cs.constant(*coeff),
+                    _ => lc + wires[wire - 1].clone() * *coeff,
+                },
+            )
+        };
+
+        for constraint in self.constraints.iter() {
+            let c = combination(cs, &constraint.c);
+            if constraint.a.is_empty() || constraint.b.is_empty() {
+                cs.constrain(c);
+                continue;
+            }
+
+            let a = combination(cs, &constraint.a);
+            let b = combination(cs, &constraint.b);
+            let (_, _, out) = cs.multiply(a, b)?;
+            cs.constrain(out - c);
+        }
+
+        Ok(wires)
+    }
+
+    /// Check that a witness assigns each wire of the circuit
+    fn check_witness(&self, witness: &CircomWitness) -> Result<(), R1CSError> {
+        if witness.values.len() != self.num_wires {
+            return Err(R1CSError::GadgetError {
+                description: "circom witness does not match the circuit's wires".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl CircomWitness {
+    /// Parse a witness from the contents of a `.wtns` file
+    pub fn from_wtns_bytes(bytes: &[u8]) -> Result<Self, R1CSError> {
+        let sections = read_sections(bytes, b"wtns", 2)?;
+
+        let mut header = Reader::new(find_section(&sections, WTNS_HEADER_SECTION)?);
+        read_field(&mut header)?;
+        let num_values = header.read_u32()? as usize;
+
+        let mut reader = Reader::new(find_section(&sections, WTNS_VALUES_SECTION)?);
+        let values = (0..num_values)
+            .map(|_| reader.read_scalar())
+            .collect::<Result<Vec<_>, _>>()?;
+        reader.finish()?;
+
+        if values.first() != Some(&Scalar::one()) {
+            return Err(R1CSError::FormatError);
+        }
+
+        Ok(Self { values })
+    }
+
+    /// The value of each wire, beginning with the constant wire
+    pub fn values(&self) -> &[Scalar] {
+        &self.values
+    }
+}
+
+/// Split a circom binary file into its sections, as pairs of a section type and contents
+fn read_sections<'a>(
+    bytes: &'a [u8],
+    magic: &[u8; 4],
+    version: u32,
+) -> Result<Vec<(u32, &'a [u8])>, R1CSError> {
+    let mut reader = Reader::new(bytes);
+    if &reader.read::<4>()? != magic || reader.read_u32()? != version {
+        return Err(R1CSError::FormatError);
+    }
+
+    let num_sections = reader.read_u32()?;
+    let sections = (0..num_sections)
+        .map(|_| {
+            let section_type = reader.read_u32()?;
+            let size = usize::try_from(reader.read_u64()?).map_err(|_| R1CSError::FormatError)?;
+            Ok((section_type, reader.read_slice(size)?))
+        })
+        .collect::<Result<Vec<_>, R1CSError>>()?;
+    reader.finish()?;
+
+    Ok(sections)
+}
+
+/// Find the unique section of the given type
+fn find_section<'a>(
+    sections: &[(u32, &'a [u8])],
+    section_type: u32,
+) -> Result<&'a [u8], R1CSError> {
+    let mut matches = sections.iter().filter(|(ty, _)| *ty == section_type);
+    match (matches.next(), matches.next()) {
+        (Some((_, contents)), None) => Ok(contents),
+        _ => Err(R1CSError::FormatError),
+    }
+}
+
+/// Read the definition of the field a circom file is defined over, failing unless it
+/// is the Stark scalar field
+fn read_field(reader: &mut Reader<'_>) -> Result<(), R1CSError> {
+    let field_size = reader.read_u32()? as usize;
+    let prime = BigUint::from_bytes_le(reader.read_slice(field_size)?);
+
+    let modulus = BigUint::from_bytes_be(&(-Scalar::one()).to_bytes_be()) + 1u8;
+    if field_size != SCALAR_BYTES || prime != modulus {
+        return Err(R1CSError::GadgetError {
+            description: "circom file is not defined over the Stark scalar field".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// A cursor over the contents of a circom file
+struct Reader<'a> {
+    /// The bytes remaining to be read
+    slice: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Create a reader over a slice
+    fn new(slice: &'a [u8]) -> Self {
+        Self { slice }
+    }
+
+    /// Read `len` bytes
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], R1CSError> {
+        if self.slice.len() < len {
+            return Err(R1CSError::FormatError);
+        }
+
+        let (bytes, rest) = self.slice.split_at(len);
+        self.slice = rest;
+        Ok(bytes)
+    }
+
+    /// Read `N` bytes
+    fn read<const N: usize>(&mut self) -> Result<[u8; N], R1CSError> {
+        self.read_slice(N).map(util::read_exact::<N>)
+    }
+
+    /// Read a little-endian `u32`
+    fn read_u32(&mut self) -> Result<u32, R1CSError> {
+        self.read::<4>().map(u32::from_le_bytes)
+    }
+
+    /// Read a little-endian `u64`
+    fn read_u64(&mut self) -> Result<u64, R1CSError> {
+        self.read::<8>().map(u64::from_le_bytes)
+    }
+
+    /// Read a little-endian field element, rejecting non-canonical encodings
+    fn read_scalar(&mut self) -> Result<Scalar, R1CSError> {
+        let mut bytes = self.read::<SCALAR_BYTES>()?;
+        bytes.reverse();
+
+        let scalar = Scalar::from_be_bytes_mod_order(&bytes);
+        if scalar.to_bytes_be()[..] != bytes[..] {
+            return Err(R1CSError::FormatError);
+        }
+
+        Ok(scalar)
+    }
+
+    /// Read a linear combination of wires, checking that each wire is in range
+    fn read_combination(&mut self, num_wires: usize) -> Result<WireCombination, R1CSError> {
+        let num_terms = self.read_u32()? as usize;
+        if self.slice.len() / (4 + SCALAR_BYTES) < num_terms {
+            return Err(R1CSError::FormatError);
+        }
+
+        (0..num_terms)
+            .map(|_| {
+                let wire = self.read_u32()? as usize;
+                if wire >= num_wires {
+                    return Err(R1CSError::FormatError);
+                }
+
+                Ok((wire, self.read_scalar()?))
+            })
+            .collect()
+    }
+
+    /// Check that every byte has been read
+    fn finish(&self) -> Result<(), R1CSError> {
+        if self.slice.is_empty() {
+            Ok(())
+        } else {
+            Err(R1CSError::FormatError)
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Constraint system traits shared by the single-prover and collaborative constraint systems
+//!
+//! The single-prover `ConstraintSystem` and the `MpcConstraintSystem` differ in their
+//! variable, linear combination and scalar types, so a gadget written against one of them
+//! cannot be applied to the other. The traits in this module abstract over these types,
+//! allowing one gadget body to target `Prover`, `Verifier` and `MpcProver`.
+
+use core::ops::{Add, Mul, Neg, Sub};
+
+use mpc_stark::algebra::{
+    authenticated_scalar::AuthenticatedScalarResult,
+    scalar::{Scalar, ScalarResult},
+};
+
+use crate::{
+    errors::{MultiproverError, R1CSError},
+    r1cs::{
+        ConstraintSystem, LinearCombination, Namespace, Prover, RandomizableConstraintSystem,
+        RandomizedConstraintSystem, RandomizingProver, RandomizingVerifier, Variable, Verifier,
+    },
+};
+
+use super::{
+    mpc_constraint_system::{
+        MpcConstraintSystem, MpcRandomizableConstraintSystem, MpcRandomizedConstraintSystem,
+    },
+    mpc_linear_combination::{MpcLinearCombination, MpcVariable},
+    mpc_prover::{MpcProver, RandomizingMpcProver},
+};
+
+/// A constraint system that is generic over the types used to build constraints
+///
+/// Gadgets written against this trait can be applied to the single prover, the verifier
+/// and the collaborative prover alike, guaranteeing that all three build the same circuit:
+///
+/// ```text
+/// fn gadget<CS: GenericConstraintSystem>(cs: &mut CS, a: CS::Variable, b: CS::Variable) {
+///     let (_, _, out) = cs.multiply(a.clone() * Scalar::from(2u64), b.into()).unwrap();
+///     cs.constrain(out - a);
+/// }
+/// ```
+pub trait GenericConstraintSystem {
+    /// The variable type allocated in the constraint system
+    type Variable: 'static
+        + Send
+        + Sync
+        + Clone
+        + Add<Self::Variable, Output = Self::LinearCombination>
+        + Add<Self::LinearCombination, Output = Self::LinearCombination>
+        + Add<Self::Scalar, Output = Self::LinearCombination>
+        + Sub<Self::Variable, Output = Self::LinearCombination>
+        + Sub<Self::LinearCombination, Output = Self::LinearCombination>
+        + Sub<Self::Scalar, Output = Self::LinearCombination>
+        + Mul<Scalar, Output = Self::LinearCombination>
+        + Mul<Self::Scalar, Output = Self::LinearCombination>;
+    /// The linear combination type that constraints are built from
+    type LinearCombination: Clone
+        + From<Self::Variable>
+        + From<Self::Scalar>
+        + Add<Self::Variable, Output = Self::LinearCombination>
+        + Add<Self::LinearCombination, Output = Self::LinearCombination>
+        + Add<Self::Scalar, Output = Self::LinearCombination>
+        + Sub<Self::Variable, Output = Self::LinearCombination>
+        + Sub<Self::LinearCombination, Output = Self::LinearCombination>
+        + Sub<Self::Scalar, Output = Self::LinearCombination>
+        + Neg<Output = Self::LinearCombination>
+        + Mul<Scalar, Output = Self::LinearCombination>
+        + Mul<Self::Scalar, Output = Self::LinearCombination>;
+    /// The public scalar type, in which challenges are sampled
+    type Scalar: 'static + Send + Sync + Clone;
+    /// The type of the values assigned to variables
+    type Value;
+
+    /// Allocate and constrain multiplication variables.
+    ///
+    /// Returns `(left, right, out)` for use in further constraints.
+    #[allow(clippy::type_complexity)]
+    fn multiply(
+        &mut self,
+        left: Self::LinearCombination,
+        right: Self::LinearCombination,
+    ) -> Result<(Self::Variable, Self::Variable, Self::Variable), R1CSError>;
+
+    /// Allocate a single variable.
+    fn allocate(&mut self, assignment: Option<Self::Value>) -> Result<Self::Variable, R1CSError>;
+
+    /// Allocate variables `left`, `right`, and `out`
+    /// with the implicit constraint that
+    /// ```text
+    /// left * right = out
+    /// ```
+    #[allow(clippy::type_complexity)]
+    fn allocate_multiplier(
+        &mut self,
+        input_assignments: Option<(Self::Value, Self::Value)>,
+    ) -> Result<(Self::Variable, Self::Variable, Self::Variable), R1CSError>;
+
+    /// Fetch the number of constraints currently registered in the constraint system
+    fn num_constraints(&self) -> usize;
+
+    /// Fetch the number of multiplication gates registered in the constraint system
+    fn num_multipliers(&self) -> usize;
+
+    /// Creates a commitment to a public input, with a blinding factor of one
+    fn commit_public(&mut self, value: Scalar) -> Self::Variable;
+
+    /// Build a linear combination holding a public constant
+    fn constant(&self, value: Scalar) -> Self::LinearCombination;
+
+    /// Enforce the explicit constraint that
+    /// ```text
+    /// lc = 0
+    /// ```
+    fn constrain(&mut self, lc: Self::LinearCombination);
+
+    /// Enforce the explicit constraint that
+    /// ```text
+    /// lc = 0
+    /// ```
+    /// attaching `label` to the constraint, so that it can be identified if unsatisfied
+    fn constrain_with_label(&mut self, lc: Self::LinearCombination, label: &str);
+
+    /// Push a namespace that prefixes the names of the constraints and variables
+    /// created until it is popped
+    fn push_namespace(&mut self, name: &str);
+
+    /// Pop the innermost namespace
+    fn pop_namespace(&mut self);
+
+    /// Push a namespace, returning a guard that pops it when dropped
+    fn namespace(&mut self, name: &str) -> Namespace<'_, Self>
+    where
+        Self: Sized,
+    {
+        self.push_namespace(name);
+        Namespace::new(self, Self::pop_namespace)
+    }
+
+    /// Evaluate a linear combination using the values allocated in the constraint system
+    fn eval(&self, lc: &Self::LinearCombination) -> Self::Value;
+}
+
+/// An extension to the generic constraint system trait that permits randomized constraints
+pub trait GenericRandomizableConstraintSystem: GenericConstraintSystem {
+    /// Represents a concrete type for the CS in a randomization phase.
+    type RandomizedCS: GenericRandomizedConstraintSystem<
+        Variable = Self::Variable,
+        LinearCombination = Self::LinearCombination,
+        Scalar = Self::Scalar,
+        Value = Self::Value,
+    >;
+
+    /// Specify additional variables and constraints randomized using a challenge scalar
+    /// bound to the assignments of the non-randomized variables.
+    ///
+    /// The callback must satisfy the bounds of both the single-prover and the
+    /// collaborative constraint systems
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Send + Sync + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>;
+}
+
+/// Represents a generic constraint system in the second phase, when the
+/// challenges can be sampled to create randomized constraints
+pub trait GenericRandomizedConstraintSystem: GenericConstraintSystem {
+    /// Generates a challenge scalar.
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Self::Scalar;
+}
+
+/// Implement the generic constraint system traits for a single-prover constraint system
+macro_rules! impl_generic_cs {
+    ($cs:ident) => {
+        impl<'t, 'g> GenericConstraintSystem for $cs<'t, 'g> {
+            type Variable = Variable;
+            type LinearCombination = LinearCombination;
+            type Scalar = Scalar;
+            type Value = Scalar;
+
+            fn multiply(
+                &mut self,
+                left: LinearCombination,
+                right: LinearCombination,
+            ) -> Result<(Variable, Variable, Variable), R1CSError> {
+                Ok(ConstraintSystem::multiply(self, left, right))
+            }
+
+            fn allocate(&mut self, assignment: Option<Scalar>) -> Result<Variable, R1CSError> {
+                ConstraintSystem::allocate(self, assignment)
+            }
+
+            fn allocate_multiplier(
+                &mut self,
+                input_assignments: Option<(Scalar, Scalar)>,
+            ) -> Result<(Variable, Variable, Variable), R1CSError> {
+                ConstraintSystem::allocate_multiplier(self, input_assignments)
+            }
+
+            fn num_constraints(&self) -> usize {
+                ConstraintSystem::num_constraints(self)
+            }
+
+            fn num_multipliers(&self) -> usize {
+                ConstraintSystem::num_multipliers(self)
+            }
+
+            fn commit_public(&mut self, value: Scalar) -> Variable {
+                ConstraintSystem::commit_public(self, value)
+            }
+
+            fn constant(&self, value: Scalar) -> LinearCombination {
+                value.into()
+            }
+
+            fn constrain(&mut self, lc: LinearCombination) {
+                ConstraintSystem::constrain(self, lc)
+            }
+
+            fn constrain_with_label(&mut self, lc: LinearCombination, label: &str) {
+                ConstraintSystem::constrain_with_label(self, lc, label)
+            }
+
+            fn push_namespace(&mut self, name: &str) {
+                ConstraintSystem::push_namespace(self, name)
+            }
+
+            fn pop_namespace(&mut self) {
+                ConstraintSystem::pop_namespace(self)
+            }
+
+            fn eval(&self, lc: &LinearCombination) -> Scalar {
+                ConstraintSystem::eval(self, lc)
+            }
+        }
+    };
+}
+
+/// Implement the generic constraint system traits for a collaborative constraint system
+macro_rules! impl_generic_mpc_cs {
+    ($cs:ident) => {
+        impl GenericConstraintSystem for $cs {
+            type Variable = MpcVariable;
+            type LinearCombination = MpcLinearCombination;
+            type Scalar = ScalarResult;
+            type Value = AuthenticatedScalarResult;
+
+            fn multiply(
+                &mut self,
+                left: MpcLinearCombination,
+                right: MpcLinearCombination,
+            ) -> Result<(MpcVariable, MpcVariable, MpcVariable), R1CSError> {
+                MpcConstraintSystem::multiply(self, &left, &right).map_err(|err| match err {
+                    MultiproverError::ProverError(err) => err,
+                    err => R1CSError::GadgetError {
+                        description: err.to_string(),
+                    },
+                })
+            }
+
+            fn allocate(
+                &mut self,
+                assignment: Option<AuthenticatedScalarResult>,
+            ) -> Result<MpcVariable, R1CSError> {
+                MpcConstraintSystem::allocate(self, assignment)
+            }
+
+            fn allocate_multiplier(
+                &mut self,
+                input_assignments: Option<(AuthenticatedScalarResult, AuthenticatedScalarResult)>,
+            ) -> Result<(MpcVariable, MpcVariable, MpcVariable), R1CSError> {
+                MpcConstraintSystem::allocate_multiplier(self, input_assignments)
+            }
+
+            fn num_constraints(&self) -> usize {
+                MpcConstraintSystem::num_constraints(self)
+            }
+
+            fn num_multipliers(&self) -> usize {
+                MpcConstraintSystem::num_multipliers(self)
+            }
+
+            fn commit_public(&mut self, value: Scalar) -> MpcVariable {
+                MpcConstraintSystem::commit_public(self, value)
+            }
+
+            fn constant(&self, value: Scalar) -> MpcLinearCombination {
+                MpcLinearCombination::from_scalar(value, self.fabric().clone())
+            }
+
+            fn constrain(&mut self, lc: MpcLinearCombination) {
+                MpcConstraintSystem::constrain(self, lc)
+            }
+
+            fn constrain_with_label(&mut self, lc: MpcLinearCombination, label: &str) {
+                MpcConstraintSystem::constrain_with_label(self, lc, label)
+            }
+
+            fn push_namespace(&mut self, name: &str) {
+                MpcConstraintSystem::push_namespace(self, name)
+            }
+
+            fn pop_namespace(&mut self) {
+                MpcConstraintSystem::pop_namespace(self)
+            }
+
+            fn eval(&self, lc: &MpcLinearCombination) -> AuthenticatedScalarResult {
+                MpcConstraintSystem::eval(self, lc)
+            }
+        }
+    };
+}
+
+impl_generic_cs!(Prover);
+impl_generic_cs!(RandomizingProver);
+impl_generic_cs!(Verifier);
+impl_generic_cs!(RandomizingVerifier);
+impl_generic_mpc_cs!(MpcProver);
+impl_generic_mpc_cs!(RandomizingMpcProver);
+
+impl<'t, 'g> GenericRandomizableConstraintSystem for Prover<'t, 'g> {
+    type RandomizedCS = RandomizingProver<'t, 'g>;
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Send + Sync + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        RandomizableConstraintSystem::specify_randomized_constraints(self, callback)
+    }
+}
+
+impl<'t, 'g> GenericRandomizableConstraintSystem for Verifier<'t, 'g> {
+    type RandomizedCS = RandomizingVerifier<'t, 'g>;
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Send + Sync + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        RandomizableConstraintSystem::specify_randomized_constraints(self, callback)
+    }
+}
+
+impl GenericRandomizableConstraintSystem for MpcProver {
+    type RandomizedCS = RandomizingMpcProver;
+
+    fn specify_randomized_constraints<F>(&mut self, callback: F) -> Result<(), R1CSError>
+    where
+        F: 'static + Send + Sync + Fn(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
+    {
+        MpcRandomizableConstraintSystem::specify_randomized_constraints(self, callback)
+    }
+}
+
+impl<'t, 'g> GenericRandomizedConstraintSystem for RandomizingProver<'t, 'g> {
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        RandomizedConstraintSystem::challenge_scalar(self, label)
+    }
+}
+
+impl<'t, 'g> GenericRandomizedConstraintSystem for RandomizingVerifier<'t, 'g> {
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        RandomizedConstraintSystem::challenge_scalar(self, label)
+    }
+}
+
+impl GenericRandomizedConstraintSystem for RandomizingMpcProver {
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> ScalarResult {
+        MpcRandomizedConstraintSystem::challenge_scalar(self, label)
+    }
+}
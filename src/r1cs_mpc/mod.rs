@@ -1,15 +1,27 @@
 mod authenticated_poly;
+mod circom;
+mod generic_constraint_system;
 mod mpc_constraint_system;
 mod mpc_inner_product;
 mod mpc_linear_combination;
 mod mpc_prover;
+#[cfg(feature = "std")]
+mod mpc_range_proof;
+mod preprocessing;
 mod proof;
 
+pub use self::circom::{CircomCircuit, CircomWitness};
+pub use self::generic_constraint_system::{
+    GenericConstraintSystem, GenericRandomizableConstraintSystem, GenericRandomizedConstraintSystem,
+};
 pub use self::mpc_constraint_system::{
     MpcConstraintSystem, MpcRandomizableConstraintSystem, MpcRandomizedConstraintSystem,
 };
 pub use self::mpc_inner_product::SharedInnerProductProof;
 pub use self::mpc_linear_combination::{MpcLinearCombination, MpcVariable};
 pub use self::mpc_prover::MpcProver;
+#[cfg(feature = "std")]
+pub use self::mpc_range_proof::SharedRangeProof;
+pub use self::preprocessing::PreprocessingCost;
 pub use self::proof::PartiallySharedR1CSProof;
-pub use crate::errors::{MultiproverError, R1CSError};
+pub use crate::errors::{MultiproverError, ProofStep, R1CSError};
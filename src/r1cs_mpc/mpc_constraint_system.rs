@@ -1,8 +1,16 @@
 //! Definition of the constraint system traits for a distributed prover
 
-use mpc_stark::algebra::{authenticated_scalar::AuthenticatedScalarResult, scalar::ScalarResult};
+use futures_util::future::BoxFuture;
+use mpc_stark::algebra::{
+    authenticated_scalar::AuthenticatedScalarResult,
+    scalar::{Scalar, ScalarResult},
+};
 
-use crate::{errors::R1CSError, transcript::MpcTranscript};
+use crate::{
+    errors::R1CSError,
+    r1cs::{CircuitNamespaces, CircuitWeights, Namespace},
+    transcript::MpcTranscript,
+};
 
 use super::{
     mpc_linear_combination::{MpcLinearCombination, MpcVariable},
@@ -27,6 +35,30 @@ pub trait MpcConstraintSystem {
     /// is not available before creation of the constraint system.
     fn transcript(&mut self) -> &mut MpcTranscript;
 
+    /// Fetch the number of constraints currently registered in the prover
+    ///
+    /// Used as a profiling metric
+    fn num_constraints(&self) -> usize;
+
+    /// Fetch the number of multiplication gates registered in the prover
+    ///
+    /// Used as a profiling metric
+    fn num_multipliers(&self) -> usize;
+
+    /// Get the sparse-reduced weight matrices & constant vector representing
+    /// the constraint system
+    ///
+    /// The weights are the public coefficients of the constraints, which are allocated
+    /// in the MPC fabric; they resolve once the fabric has evaluated them. The result
+    /// matches `ConstraintSystem::get_weights` for the same circuit
+    fn get_weights(&self) -> BoxFuture<'static, CircuitWeights>;
+
+    /// Get the namespaces recorded for the constraints and variables of the
+    /// constraint system, indexed in the same way as the weights
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        CircuitNamespaces::default()
+    }
+
     /// Allocate and constrain multiplication variables.
     ///
     /// Allocate variables `left`, `right`, and `out`
@@ -79,12 +111,48 @@ pub trait MpcConstraintSystem {
     /// Counts the amount of allocated multipliers.
     fn multipliers_len(&self) -> usize;
 
+    /// Creates a commitment to a public input (also referred to as a "statement variable")
+    ///
+    /// The value is known to both parties and committed with a blinding factor of one, so
+    /// that a verifier can reproduce the commitment with `ConstraintSystem::commit_public`.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `MpcVariable` that can be used to refer to this commited value in constraint
+    /// generation.
+    fn commit_public(&mut self, value: Scalar) -> MpcVariable;
+
     /// Enforce the explicit constraint that
     /// ```text
     /// lc = 0
     /// ```
     fn constrain(&mut self, lc: MpcLinearCombination);
 
+    /// Enforce the explicit constraint that
+    /// ```text
+    /// lc = 0
+    /// ```
+    /// attaching `label` to the constraint, so that it can be identified if unsatisfied
+    fn constrain_with_label(&mut self, lc: MpcLinearCombination, _label: &str) {
+        self.constrain(lc)
+    }
+
+    /// Push a namespace that prefixes the names of the constraints and variables
+    /// created until it is popped
+    fn push_namespace(&mut self, _name: &str) {}
+
+    /// Pop the innermost namespace
+    fn pop_namespace(&mut self) {}
+
+    /// Push a namespace, returning a guard that pops it when dropped
+    fn namespace(&mut self, name: &str) -> Namespace<'_, Self>
+    where
+        Self: Sized,
+    {
+        self.push_namespace(name);
+        Namespace::new(self, Self::pop_namespace)
+    }
+
     /// Evaluate a linear combination using the values allocated in the constraint system
     fn eval(&self, lc: &MpcLinearCombination) -> AuthenticatedScalarResult;
 }
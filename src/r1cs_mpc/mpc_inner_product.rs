@@ -13,11 +13,13 @@ use mpc_stark::algebra::authenticated_stark_point::AuthenticatedStarkPointOpenRe
 use mpc_stark::algebra::scalar::{Scalar, ScalarResult};
 use mpc_stark::algebra::stark_curve::{StarkPoint, StarkPointResult};
 use mpc_stark::error::MpcError;
+use mpc_stark::network::PartyId;
 use mpc_stark::MpcFabric;
+use rand::thread_rng;
 
 use core::iter;
 
-use crate::errors::MultiproverError;
+use crate::errors::{MultiproverError, ProofStep};
 use crate::inner_product_proof::InnerProductProof;
 use crate::transcript::MpcTranscript;
 
@@ -38,6 +40,8 @@ pub struct SharedInnerProductProof {
     pub(crate) b: AuthenticatedScalarResult,
     #[cfg(feature = "integration_test")]
     pub b: AuthenticatedScalarResult,
+    /// The ID of the local party, used to attribute failed openings
+    pub(crate) party_id: PartyId,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -224,6 +228,7 @@ impl SharedInnerProductProof {
             R_vec,
             a: a[0].clone(),
             b: b[0].clone(),
+            party_id: fabric.party_id(),
         })
     }
 
@@ -245,30 +250,98 @@ impl SharedInnerProductProof {
     pub async fn open(&self) -> Result<InnerProductProof, MultiproverError> {
         // Open the scalars (a, b)
         // The Ristretto points are already opened as a result of running the protocol
-        let a = self
-            .a
-            .open_authenticated()
-            .await
-            .map_err(MultiproverError::Mpc)?;
-        let b = self
-            .b
-            .open_authenticated()
-            .await
-            .map_err(MultiproverError::Mpc)?;
-
-        let L_vec = join_all(self.L_vec.iter().cloned())
-            .await
-            .into_iter()
-            .collect::<Result<Vec<_>, MpcError>>()
-            .map_err(MultiproverError::Mpc)?;
-        let R_vec = join_all(self.R_vec.iter().cloned())
-            .await
-            .into_iter()
-            .collect::<Result<Vec<_>, MpcError>>()
-            .map_err(MultiproverError::Mpc)?;
+        let (a, b) = futures::join!(self.a.open_authenticated(), self.b.open_authenticated());
+        let map_err = |err: MpcError| self.opening_error(err, ProofStep::InnerProductScalars);
+        let (a, b) = (a.map_err(map_err)?, b.map_err(map_err)?);
 
+        let (L_vec, R_vec) = self.check_rounds().await?;
         Ok(InnerProductProof { L_vec, R_vec, a, b })
     }
+
+    /// Opens the proof to `party` only
+    ///
+    /// The scalars `a` and `b` are opened privately to `party`, after their MACs have
+    /// been checked, so that only `party` learns the full proof. Returns `None` on
+    /// every other party
+    pub async fn open_to(
+        &self,
+        party: PartyId,
+    ) -> Result<Option<InnerProductProof>, MultiproverError> {
+        let opened =
+            open_authenticated_to(&[self.a.clone(), self.b.clone()], party, self.a.fabric())
+                .await
+                .map_err(|err| self.opening_error(err, ProofStep::InnerProductScalars))?;
+
+        let (L_vec, R_vec) = self.check_rounds().await?;
+        Ok(opened.map(|ab| InnerProductProof {
+            L_vec,
+            R_vec,
+            a: ab[0],
+            b: ab[1],
+        }))
+    }
+
+    /// Await the `L` and `R` commitments, which were opened while running the protocol
+    ///
+    /// The commitments are checked round by round, so that a failed MAC check is
+    /// attributed to the round in which the inconsistent shares were sent
+    async fn check_rounds(&self) -> Result<(Vec<StarkPoint>, Vec<StarkPoint>), MultiproverError> {
+        let mut L_vec = Vec::with_capacity(self.L_vec.len());
+        let mut R_vec = Vec::with_capacity(self.R_vec.len());
+        for (round, (L, R)) in self.L_vec.iter().zip(self.R_vec.iter()).enumerate() {
+            let (L, R) = futures::join!(L.clone(), R.clone());
+            let map_err =
+                |err: MpcError| self.opening_error(err, ProofStep::InnerProductRound(round));
+
+            L_vec.push(L.map_err(map_err)?);
+            R_vec.push(R.map_err(map_err)?);
+        }
+
+        Ok((L_vec, R_vec))
+    }
+
+    /// Attribute an error opening the values sent at `step` to the responsible party
+    fn opening_error(&self, err: MpcError, step: ProofStep) -> MultiproverError {
+        MultiproverError::from_opening(err, step, self.party_id)
+    }
+}
+
+/// Opens a batch of shared values to `party` only
+///
+/// Each value is masked by a random value that `party` inputs to the MPC, and the masked
+/// values are opened with their MACs checked. The masked values reveal nothing to the
+/// other parties; only `party` can remove the masks. Returns `None` on every other party
+pub(crate) async fn open_authenticated_to(
+    values: &[AuthenticatedScalarResult],
+    party: PartyId,
+    fabric: &MpcFabric,
+) -> Result<Option<Vec<Scalar>>, MpcError> {
+    let mut rng = thread_rng();
+    let masks = (0..values.len())
+        .map(|_| Scalar::random(&mut rng))
+        .collect::<Vec<_>>();
+    let shared_masks = fabric.batch_share_scalar(masks.clone(), party);
+
+    let masked_values = values
+        .iter()
+        .zip(shared_masks.iter())
+        .map(|(value, mask)| (value + mask).open_authenticated());
+    let masked_values = join_all(masked_values)
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, MpcError>>()?;
+
+    if fabric.party_id() != party {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        masked_values
+            .into_iter()
+            .zip(masks)
+            .map(|(masked_value, mask)| masked_value - mask)
+            .collect(),
+    ))
 }
 
 /// Computes an inner product of two vectors
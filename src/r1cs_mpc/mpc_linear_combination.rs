@@ -1,15 +1,17 @@
 //! Definition of linear combinations.
 
 use core::fmt::{Debug, Formatter, Result};
+use core::future::Future;
 use core::hash::Hash;
 use core::ops::{AddAssign, SubAssign};
+use futures_util::future::join_all;
 use mpc_stark::algebra::scalar::{Scalar, ScalarResult};
 use mpc_stark::MpcFabric;
 use std::collections::HashMap;
 use std::iter::FromIterator;
 use std::ops::{Add, Mul, Neg, Sub};
 
-use crate::r1cs::Variable;
+use crate::r1cs::{LinearCombination, Variable};
 
 /// Represents a variable in a constraint system.
 pub struct MpcVariable {
@@ -147,10 +149,13 @@ impl<'a, L: Into<MpcLinearCombination>> Sub<L> for &'a MpcVariable {
     }
 }
 
-impl<S: Into<Scalar>> Mul<S> for MpcVariable {
+// Variables are multiplied by public scalars, or by challenges sampled in the MPC. The
+// impls are written out per type, as a blanket impl over `Into<Scalar>` would overlap
+// with the impls for `ScalarResult`
+impl Mul<Scalar> for MpcVariable {
     type Output = MpcLinearCombination;
 
-    fn mul(self, other: S) -> Self::Output {
+    fn mul(self, other: Scalar) -> Self::Output {
         let coeff = self.fabric.allocate_scalar(other);
         MpcLinearCombination {
             terms: HashMap::from([(self, coeff)]),
@@ -158,6 +163,32 @@ impl<S: Into<Scalar>> Mul<S> for MpcVariable {
     }
 }
 
+impl Mul<u64> for MpcVariable {
+    type Output = MpcLinearCombination;
+
+    fn mul(self, other: u64) -> Self::Output {
+        self * Scalar::from(other)
+    }
+}
+
+impl Mul<ScalarResult> for MpcVariable {
+    type Output = MpcLinearCombination;
+
+    fn mul(self, other: ScalarResult) -> Self::Output {
+        MpcLinearCombination {
+            terms: HashMap::from([(self, other)]),
+        }
+    }
+}
+
+impl<'a> Mul<&'a ScalarResult> for MpcVariable {
+    type Output = MpcLinearCombination;
+
+    fn mul(self, other: &'a ScalarResult) -> Self::Output {
+        self * other.clone()
+    }
+}
+
 // Arithmetic on scalars with variables produces linear combinations
 impl<'a> Add<&'a MpcVariable> for Scalar {
     type Output = MpcLinearCombination;
@@ -267,6 +298,21 @@ impl MpcLinearCombination {
             )]),
         }
     }
+
+    /// Resolve the public coefficients of the linear combination, giving the
+    /// equivalent single-prover `LinearCombination`
+    ///
+    /// The coefficients are allocated in the fabric, so the result is available
+    /// once the fabric has evaluated them
+    pub fn to_linear_combination(&self) -> impl Future<Output = LinearCombination> + Send {
+        let (vars, coeffs): (Vec<Variable>, Vec<ScalarResult>) = self
+            .terms
+            .iter()
+            .map(|(var, coeff)| (var.get_type(), coeff.clone()))
+            .unzip();
+
+        async move { vars.into_iter().zip(join_all(coeffs).await).collect() }
+    }
 }
 
 impl FromIterator<(MpcVariable, Scalar)> for MpcLinearCombination {
@@ -390,11 +436,37 @@ impl<'a> Neg for &'a MpcLinearCombination {
     }
 }
 
-impl<S: Into<Scalar>> Mul<S> for MpcLinearCombination {
+impl Mul<Scalar> for MpcLinearCombination {
+    type Output = Self;
+
+    fn mul(mut self, other: Scalar) -> Self::Output {
+        for (_, s) in self.terms.iter_mut() {
+            *s = &*s * other
+        }
+        self
+    }
+}
+
+impl Mul<u64> for MpcLinearCombination {
+    type Output = Self;
+
+    fn mul(self, other: u64) -> Self::Output {
+        self * Scalar::from(other)
+    }
+}
+
+impl Mul<ScalarResult> for MpcLinearCombination {
+    type Output = Self;
+
+    fn mul(self, other: ScalarResult) -> Self::Output {
+        self * &other
+    }
+}
+
+impl<'a> Mul<&'a ScalarResult> for MpcLinearCombination {
     type Output = Self;
 
-    fn mul(mut self, other: S) -> Self::Output {
-        let other = other.into();
+    fn mul(mut self, other: &'a ScalarResult) -> Self::Output {
         for (_, s) in self.terms.iter_mut() {
             *s = &*s * other
         }
@@ -3,12 +3,12 @@
 use core::{borrow::BorrowMut, iter};
 
 use crate::{
-    errors::{MultiproverError, R1CSError},
-    r1cs::Variable,
+    errors::{MultiproverError, ProofStep, R1CSError},
+    r1cs::{CircuitNamespaces, CircuitWeights, Namespaces, UnsatisfiedConstraint, Variable},
     transcript::MpcTranscript,
     util, BulletproofGens, PedersenGens,
 };
-use futures_util::future::join_all;
+use futures_util::future::{join_all, BoxFuture};
 use itertools::Itertools;
 use merlin::HashChainTranscript as Transcript;
 use mpc_stark::{
@@ -22,7 +22,7 @@ use mpc_stark::{
     },
     beaver::SharedValueSource,
     error::MpcError,
-    network::{PartyId, QuicTwoPartyNet},
+    network::{MpcNetwork, PartyId},
     MpcFabric,
 };
 
@@ -33,6 +33,7 @@ use super::{
     },
     mpc_inner_product::SharedInnerProductProof,
     mpc_linear_combination::{MpcLinearCombination, MpcVariable},
+    preprocessing::PreprocessingCost,
     PartiallySharedR1CSProof,
 };
 
@@ -63,6 +64,8 @@ pub struct MpcProver {
     pc_gens: PedersenGens,
     /// Teh constraints accumulated so far.
     constraints: Vec<MpcLinearCombination>,
+    /// The namespaces of the constraints and variables
+    namespaces: Namespaces,
     /// Stores assignments to the "left" of multiplication gates.
     a_L: Vec<AuthenticatedScalarResult>,
     /// Stores assignments to the "right" of multiplication gates.
@@ -72,8 +75,6 @@ pub struct MpcProver {
     /// High-level witness assignments (value openings to V commitments)
     /// where we use a pedersen commitment `value * G + blinding * H`
     v: Vec<AuthenticatedScalarResult>,
-    /// High-level public variables that are allocated in hte constraint system
-    v_public: Vec<ScalarResult>,
     /// High level witness data (blinding openings to V commitments)
     v_blinding: Vec<AuthenticatedScalarResult>,
     /// Index of a pending multiplier that hasn't been assigned yet
@@ -97,8 +98,11 @@ pub struct RandomizingMpcProver {
 
 impl MpcProver {
     /// Create a new MpcProver with a custom network
-    pub fn new_with_network<S: 'static + SharedValueSource>(
-        network: QuicTwoPartyNet,
+    ///
+    /// The network may be any transport the `MpcFabric` accepts; a fabric is
+    /// built over it and owned by the prover
+    pub fn new_with_network<N: 'static + MpcNetwork, S: 'static + SharedValueSource>(
+        network: N,
         beaver_source: S,
         transcript: Transcript,
         pc_gens: PedersenGens,
@@ -115,11 +119,11 @@ impl MpcProver {
             pc_gens,
             fabric,
             constraints: Vec::new(),
+            namespaces: Namespaces::default(),
             a_L: Vec::new(),
             a_R: Vec::new(),
             a_O: Vec::new(),
             v: Vec::new(),
-            v_public: Vec::new(),
             v_blinding: Vec::new(),
             deferred_constraints: Vec::new(),
             pending_multiplier: None,
@@ -140,36 +144,39 @@ impl MpcProver {
             pc_gens,
             fabric,
             constraints: Vec::new(),
+            namespaces: Namespaces::default(),
             a_L: Vec::new(),
             a_R: Vec::new(),
             a_O: Vec::new(),
             v: Vec::new(),
-            v_public: Vec::new(),
             v_blinding: Vec::new(),
             deferred_constraints: Vec::new(),
             pending_multiplier: None,
         }
     }
 
-    /// Fetch the number of constraints currently registered in the prover
-    ///
-    /// Used as a profiling metric
-    #[cfg(feature = "benchmarking")]
-    pub fn num_constraints(&self) -> usize {
-        self.constraints.len()
+    /// Get the party ID of the local party in the MPC network
+    pub fn party_id(&self) -> u64 {
+        self.fabric.party_id()
     }
 
-    /// Fetch the number of multiplication gates registered in the prover
+    /// Compute the preprocessing that `prove` will consume for the circuit built so far
     ///
-    /// Used as a profiling metric
-    #[cfg(feature = "benchmarking")]
-    pub fn num_multipliers(&self) -> usize {
-        self.a_O.len()
+    /// Returns `None` if the circuit specifies randomized constraints, as the multipliers
+    /// they allocate are not known until the proof is generated; such circuits should
+    /// use `PreprocessingCost::new` with the number of multipliers those constraints
+    /// allocate.
+    pub fn preprocessing_cost(&self) -> Option<PreprocessingCost> {
+        if !self.deferred_constraints.is_empty() {
+            return None;
+        }
+
+        Some(PreprocessingCost::new(self.a_L.len(), 0, self.v.len()))
     }
 
-    /// Get the party ID of the local party in the MPC network
-    pub fn party_id(&self) -> u64 {
-        self.fabric.party_id()
+    /// Get a reference to the underlying MPC fabric
+    pub(crate) fn fabric(&self) -> &MpcFabric {
+        &self.fabric
     }
 }
 
@@ -179,6 +186,28 @@ impl MpcConstraintSystem for MpcProver {
         self.transcript.borrow_mut()
     }
 
+    fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    fn num_multipliers(&self) -> usize {
+        self.a_O.len()
+    }
+
+    fn get_weights(&self) -> BoxFuture<'static, CircuitWeights> {
+        let constraints = self
+            .constraints
+            .iter()
+            .map(MpcLinearCombination::to_linear_combination)
+            .collect_vec();
+
+        Box::pin(async move { CircuitWeights::from_constraints(&join_all(constraints).await) })
+    }
+
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.namespaces.recorded().clone()
+    }
+
     #[allow(unused_variables)]
     fn multiply(
         &mut self,
@@ -204,6 +233,7 @@ impl MpcConstraintSystem for MpcProver {
         );
 
         // Add the value assignments
+        self.namespaces.record_multiplier(self.a_L.len());
         self.a_L.push(l);
         self.a_R.push(r);
         self.a_O.push(o);
@@ -224,7 +254,9 @@ impl MpcConstraintSystem for MpcProver {
         assignment: Option<AuthenticatedScalarResult>,
     ) -> Result<MpcVariable, R1CSError> {
         // Allocate a scalar in the MPC network, assume public visibility
-        let scalar = assignment.ok_or(R1CSError::MissingAssignment)?;
+        let scalar =
+            assignment.ok_or_else(|| self.namespaces.wrap_error(R1CSError::MissingAssignment))?;
+        self.namespaces.record_allocation();
 
         // If there is a pending multiplier, allocate this scalar as the right
         // hand side of the multiplication gate
@@ -232,6 +264,7 @@ impl MpcConstraintSystem for MpcProver {
             None => {
                 let i = self.a_L.len();
                 self.pending_multiplier = Some(i);
+                self.namespaces.record_multiplier(i);
                 self.a_L.push(scalar);
                 let allocated_zero = self.fabric.zero_authenticated();
                 self.a_R.push(allocated_zero.clone());
@@ -258,9 +291,11 @@ impl MpcConstraintSystem for MpcProver {
         input_assignments: Option<(AuthenticatedScalarResult, AuthenticatedScalarResult)>,
     ) -> Result<(MpcVariable, MpcVariable, MpcVariable), R1CSError> {
         // Allocate a scalar in the MPC network, assume public visibility
-        let (left, right) = input_assignments.ok_or(R1CSError::MissingAssignment)?;
+        let (left, right) = input_assignments
+            .ok_or_else(|| self.namespaces.wrap_error(R1CSError::MissingAssignment))?;
 
         // Allocate the output of the multiplication gate
+        self.namespaces.record_multiplier(self.a_L.len());
         self.a_O.push(&left * &right);
         self.a_L.push(left);
         self.a_R.push(right);
@@ -285,10 +320,36 @@ impl MpcConstraintSystem for MpcProver {
         self.a_L.len()
     }
 
+    /// Creates a commitment to a public (statement) variable. As in the single-prover
+    /// constraint system, the commitment uses a constant "blinding" factor of one so that
+    /// the verifier can mimic it when verifying the proof.
+    fn commit_public(&mut self, value: Scalar) -> MpcVariable {
+        let v = &self.fabric.allocate_scalar(value) * self.fabric.one_authenticated();
+        let v_blinding = self.fabric.one_authenticated();
+
+        self.commit_shared_value(&v, &v_blinding).1
+    }
+
     fn constrain(&mut self, lc: MpcLinearCombination) {
+        self.namespaces
+            .record_constraint(self.constraints.len(), None);
+        self.constraints.push(lc)
+    }
+
+    fn constrain_with_label(&mut self, lc: MpcLinearCombination, label: &str) {
+        self.namespaces
+            .record_constraint(self.constraints.len(), Some(label));
         self.constraints.push(lc)
     }
 
+    fn push_namespace(&mut self, name: &str) {
+        self.namespaces.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespaces.pop_namespace()
+    }
+
     /// Evaluate a linear combination of allocated variables
     fn eval(&self, lc: &MpcLinearCombination) -> AuthenticatedScalarResult {
         self.eval_lc(lc)
@@ -302,16 +363,47 @@ impl MpcRandomizableConstraintSystem for MpcProver {
     where
         F: 'static + Send + Sync + FnOnce(&mut Self::RandomizedCS) -> Result<(), R1CSError>,
     {
-        self.deferred_constraints.push(Box::new(callback));
+        // Run the callback within the namespaces it was specified in
+        let scope = self.namespaces.scope();
+        self.deferred_constraints
+            .push(Box::new(move |cs: &mut RandomizingMpcProver| {
+                cs.prover.namespaces.enter(&scope);
+                let res = callback(cs);
+                cs.prover.namespaces.exit(&scope);
+                res
+            }));
         Ok(())
     }
 }
 
+impl RandomizingMpcProver {
+    /// Get a reference to the underlying MPC fabric
+    pub(crate) fn fabric(&self) -> &MpcFabric {
+        self.prover.fabric()
+    }
+}
+
 impl MpcConstraintSystem for RandomizingMpcProver {
     fn transcript(&mut self) -> &mut MpcTranscript {
         self.prover.transcript()
     }
 
+    fn num_constraints(&self) -> usize {
+        self.prover.num_constraints()
+    }
+
+    fn num_multipliers(&self) -> usize {
+        self.prover.num_multipliers()
+    }
+
+    fn get_weights(&self) -> BoxFuture<'static, CircuitWeights> {
+        self.prover.get_weights()
+    }
+
+    fn get_namespaces(&self) -> CircuitNamespaces {
+        self.prover.get_namespaces()
+    }
+
     fn multiply(
         &mut self,
         left: &MpcLinearCombination,
@@ -338,10 +430,26 @@ impl MpcConstraintSystem for RandomizingMpcProver {
         self.prover.multipliers_len()
     }
 
+    fn commit_public(&mut self, value: Scalar) -> MpcVariable {
+        self.prover.commit_public(value)
+    }
+
     fn constrain(&mut self, lc: MpcLinearCombination) {
         self.prover.constrain(lc)
     }
 
+    fn constrain_with_label(&mut self, lc: MpcLinearCombination, label: &str) {
+        self.prover.constrain_with_label(lc, label)
+    }
+
+    fn push_namespace(&mut self, name: &str) {
+        self.prover.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.prover.pop_namespace()
+    }
+
     fn eval(&self, lc: &MpcLinearCombination) -> AuthenticatedScalarResult {
         self.prover.eval(lc)
     }
@@ -404,6 +512,18 @@ impl MpcProver {
         v: &AuthenticatedScalarResult,
         v_blinding: &AuthenticatedScalarResult,
     ) -> Result<(AuthenticatedStarkPointOpenResult, MpcVariable), MpcError> {
+        Ok(self.commit_shared_value(v, v_blinding))
+    }
+
+    /// Commit to a shared value and allocate it in the constraint system
+    ///
+    /// The opening of the commitment is lazy, so this has no failure path of its own;
+    /// a failed opening surfaces when the returned result is awaited.
+    fn commit_shared_value(
+        &mut self,
+        v: &AuthenticatedScalarResult,
+        v_blinding: &AuthenticatedScalarResult,
+    ) -> (AuthenticatedStarkPointOpenResult, MpcVariable) {
         // Commit to the input, open the commitment, and add the commitment to the transcript.
         let value_commit = self
             .pc_gens
@@ -413,13 +533,14 @@ impl MpcProver {
 
         // Add the value to the constraint system
         let i = self.v.len();
+        self.namespaces.record_commitment(i);
         self.v.push(v.clone());
         self.v_blinding.push(v_blinding.clone());
 
-        Ok((
+        (
             value_commit,
             MpcVariable::new_with_type(Variable::Committed(i), self.fabric.clone()),
-        ))
+        )
     }
 
     /// Commit to a batch of pre-shared values
@@ -444,6 +565,7 @@ impl MpcProver {
             .for_each(|blinder| self.transcript.append_point(b"V", &blinder.value));
 
         let i = self.v.len();
+        (i..i + n).for_each(|i| self.namespaces.record_commitment(i));
         self.v.append(&mut v.to_vec());
         self.v_blinding.append(&mut v_blinding.to_vec());
 
@@ -516,6 +638,7 @@ impl MpcProver {
     fn create_randomized_constraints(mut self) -> Result<Self, R1CSError> {
         // Clear the pending multiplier (if any) because it was committed into A_L/A_R/S.
         self.pending_multiplier = None;
+        self.namespaces.begin_randomized_phase();
 
         if self.deferred_constraints.is_empty() {
             self.transcript.r1cs_1phase_domain_sep();
@@ -554,17 +677,30 @@ impl MpcProver {
 
     /// Checks whether all the constraints are satisfied, does not prove the statement
     pub async fn constraints_satisfied(&self) -> bool {
+        self.unsatisfied_constraints().await.is_empty()
+    }
+
+    /// Find the constraints that are not satisfied by the current assignments, along
+    /// with their labels
+    ///
+    /// The evaluations of the constraints are opened to both parties. Randomized
+    /// constraints are only added when proving, so they are not checked
+    pub async fn unsatisfied_constraints(&self) -> Vec<UnsatisfiedConstraint> {
         let mut evals = Vec::with_capacity(self.constraints.len());
         for constraint in self.constraints.iter() {
             evals.push(self.eval_lc(constraint));
         }
 
-        // Check that all the constraints are satisfied
+        // Open the evaluations and find the non-zero ones
         let open_results = AuthenticatedScalarResult::open_batch(&evals);
-        join_all(open_results)
+        let indices = join_all(open_results)
             .await
             .into_iter()
-            .all(|res| res == Scalar::zero())
+            .enumerate()
+            .filter(|(_, res)| *res != Scalar::zero())
+            .map(|(i, _)| i);
+
+        self.namespaces.unsatisfied(indices)
     }
 
     /// Consume this `ConstraintSystem` and produce a shared proof
@@ -575,6 +711,10 @@ impl MpcProver {
     /// as they derive Fiat-Shamir challenges from these transcripts. This is simpler
     /// than deriving the challenges in secret sharing space as we would have to hash
     /// within the MPC circuit, and implement a hasher on top of the authenticated field.
+    ///
+    /// These openings are authenticated; their MAC checks are deferred until the proof
+    /// is opened, at which point a failed check is reported as
+    /// `MultiproverError::InconsistentShares`, naming the step that failed.
     #[allow(non_snake_case)]
     pub fn prove(
         mut self,
@@ -627,7 +767,7 @@ impl MpcProver {
                 .chain(gens.G(n1).copied())
                 .chain(gens.H(n1).copied()),
         )
-        .open();
+        .open_authenticated();
 
         // Construct a commitment to the multiplication gate outputs a_O
         // This commitment has the form
@@ -638,7 +778,7 @@ impl MpcProver {
             iter::once(&o_blinding1).chain(self.a_O.iter()).cloned(),
             iter::once(B_blinding).chain(gens.G(n1).copied()),
         )
-        .open();
+        .open_authenticated();
 
         // Construct a commitment to the blinding factors used in the inner product proofs
         // This commitment has the form
@@ -654,12 +794,18 @@ impl MpcProver {
                 .chain(gens.G(n1).copied())
                 .chain(gens.H(n1).copied()),
         )
-        .open();
+        .open_authenticated();
 
         // Add the commitments to the transcript, these are used to generate Fiat-Shamir challenges
-        self.transcript.append_point(b"A_I1", &A_I1);
-        self.transcript.append_point(b"A_O1", &A_O1);
-        self.transcript.append_point(b"S1", &S1);
+        self.transcript.append_point(b"A_I1", &A_I1.value);
+        self.transcript.append_point(b"A_O1", &A_O1.value);
+        self.transcript.append_point(b"S1", &S1.value);
+
+        // Record the authenticated openings, their MAC checks are awaited when the proof is opened
+        let mut point_openings = [A_I1.clone(), A_O1.clone(), S1.clone()]
+            .into_iter()
+            .map(|opening| (ProofStep::PhaseOneCommitments, opening))
+            .collect_vec();
 
         // Begin phase 2 of the commitments
         // In this phase, we have initialized the Fiat-Shamir transcript with the commitments
@@ -750,13 +896,20 @@ impl MpcProver {
             );
 
             // Batch open the values
-            let mut opened_values =
-                AuthenticatedStarkPointResult::open_batch(&[shared_A_I, shared_A_O, shared_S]);
+            let mut opened_values = AuthenticatedStarkPointResult::open_authenticated_batch(&[
+                shared_A_I, shared_A_O, shared_S,
+            ]);
+            point_openings.extend(
+                opened_values
+                    .iter()
+                    .cloned()
+                    .map(|opening| (ProofStep::PhaseTwoCommitments, opening)),
+            );
 
             (
-                opened_values.remove(0),
-                opened_values.remove(0),
-                opened_values.remove(0),
+                opened_values.remove(0).value,
+                opened_values.remove(0).value,
+                opened_values.remove(0).value,
             )
         } else {
             (
@@ -855,6 +1008,12 @@ impl MpcProver {
             let mut opened_values = AuthenticatedStarkPointResult::open_authenticated_batch(&[
                 t_1_shared, t_3_shared, t_4_shared, t_5_shared, t_6_shared,
             ]);
+            point_openings.extend(
+                opened_values
+                    .iter()
+                    .cloned()
+                    .map(|opening| (ProofStep::PolynomialCommitments, opening)),
+            );
 
             (
                 opened_values.remove(0),
@@ -925,14 +1084,20 @@ impl MpcProver {
         let e_blinding = &x * (i_blinding + &x * (o_blinding + &x * s_blinding));
 
         // Open the final set of transcript values
-        let (t_x_open, t_x_blinding_open, e_blinding_open) = {
-            let mut opened_values =
-                AuthenticatedScalarResult::open_batch(&[t_x, t_x_blinding, e_blinding]);
+        let (t_x_open, t_x_blinding_open, e_blinding_open, scalar_openings) = {
+            let opened_values = [t_x, t_x_blinding, e_blinding]
+                .iter()
+                .map(|value| value.open_authenticated())
+                .collect_vec();
 
             (
-                opened_values.remove(0),
-                opened_values.remove(0),
-                opened_values.remove(0),
+                opened_values[0].value.clone(),
+                opened_values[1].value.clone(),
+                opened_values[2].value.clone(),
+                opened_values
+                    .into_iter()
+                    .map(|opening| (ProofStep::PolynomialEvaluation, opening))
+                    .collect_vec(),
             )
         };
 
@@ -973,9 +1138,9 @@ impl MpcProver {
         )?;
 
         Ok(PartiallySharedR1CSProof {
-            A_I1,
-            A_O1,
-            S1,
+            A_I1: A_I1.value,
+            A_O1: A_O1.value,
+            S1: S1.value,
             A_I2,
             A_O2,
             S2,
@@ -988,6 +1153,9 @@ impl MpcProver {
             t_x_blinding: t_x_blinding_open,
             e_blinding: e_blinding_open,
             ipp_proof: ipp,
+            point_openings,
+            scalar_openings,
+            party_id: self.fabric.party_id(),
         })
     }
 }
@@ -0,0 +1,466 @@
+//! Groups the implementation of range proofs over values that are secret shared
+//! between the proving parties
+
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+
+use mpc_stark::algebra::authenticated_scalar::{
+    AuthenticatedScalarOpenResult, AuthenticatedScalarResult,
+};
+use mpc_stark::algebra::authenticated_stark_point::{
+    AuthenticatedStarkPointOpenResult, AuthenticatedStarkPointResult,
+};
+use mpc_stark::algebra::scalar::{Scalar, ScalarResult};
+use mpc_stark::algebra::stark_curve::StarkPoint;
+use mpc_stark::error::MpcError;
+use mpc_stark::network::PartyId;
+use mpc_stark::MpcFabric;
+
+use crate::errors::{MultiproverError, ProofError, ProofStep};
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::transcript::MpcTranscript;
+use crate::util;
+
+use super::mpc_inner_product::{authenticated_inner_product, SharedInnerProductProof};
+
+/// A range proof over values that are secret shared between multiple proving parties.
+///
+/// The proof is partially shared: the commitments and the scalars appended to the
+/// transcript are opened during proof generation so that the parties derive the same
+/// Fiat-Shamir challenges; only the inner product proof remains shared until `open`.
+/// The MACs of the opened values are checked when the proof is opened.
+///
+/// This type does not include a verifier implementation; once opened, the proof is a
+/// standard [`RangeProof`] and should be verified with [`RangeProof::verify_multiple`].
+#[derive(Clone, Debug)]
+pub struct SharedRangeProof {
+    /// Commitment to the bits of the values
+    pub(crate) A: AuthenticatedStarkPointOpenResult,
+    /// Commitment to the blinding factors
+    pub(crate) S: AuthenticatedStarkPointOpenResult,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub(crate) T_1: AuthenticatedStarkPointOpenResult,
+    /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
+    pub(crate) T_2: AuthenticatedStarkPointOpenResult,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    pub(crate) t_x: AuthenticatedScalarOpenResult,
+    /// Blinding factor for the synthetic commitment to \\(t(x)\\)
+    pub(crate) t_x_blinding: AuthenticatedScalarOpenResult,
+    /// Blinding factor for the synthetic commitment to the inner-product arguments
+    pub(crate) e_blinding: AuthenticatedScalarOpenResult,
+    /// Proof data for the inner-product argument.
+    pub(crate) ipp_proof: SharedInnerProductProof,
+    /// The ID of the local party, used to attribute failed openings
+    pub(crate) party_id: PartyId,
+}
+
+impl SharedRangeProof {
+    /// Create a collaborative rangeproof for a single shared value `v` with
+    /// shared blinding scalar `v_blinding`.
+    /// This is a convenience wrapper around [`SharedRangeProof::prove_multiple`].
+    pub async fn prove_single(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut MpcTranscript,
+        v: &AuthenticatedScalarResult,
+        v_blinding: &AuthenticatedScalarResult,
+        n: usize,
+        fabric: &MpcFabric,
+    ) -> Result<(SharedRangeProof, AuthenticatedStarkPointOpenResult), MultiproverError> {
+        let (proof, mut commitments) = SharedRangeProof::prove_multiple(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v.clone()],
+            &[v_blinding.clone()],
+            n,
+            fabric,
+        )
+        .await?;
+
+        Ok((proof, commitments.remove(0)))
+    }
+
+    /// Create a collaborative rangeproof for a set of shared values.
+    ///
+    /// No party needs to know the values in the clear: each value is decomposed into
+    /// its `n` low-order bits inside the MPC. The value is masked by a shared random
+    /// value built from `n + 128` shared bits, the masked value is opened, and the bits
+    /// of the value are recovered by subtracting the shared bits of the mask from the
+    /// public bits of the masked value. The opening hides a value in \\([0, 2^n)\\) up
+    /// to a statistical distance of \\(2^{-128}\\). A value outside of the range yields
+    /// a proof that fails verification, and its masked opening may reveal information
+    /// about it.
+    ///
+    /// The masked values are opened and their MACs checked before the proof is
+    /// generated, so a party that sends inconsistent shares of them is detected here,
+    /// with [`ProofStep::BitDecomposition`].
+    ///
+    /// The transcript is driven in the same order as [`RangeProof::prove_multiple`],
+    /// so the opened proof verifies against a verifier transcript with the same seed.
+    /// Returns the shared proof along with the opened commitments to the values.
+    pub async fn prove_multiple(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut MpcTranscript,
+        values: &[AuthenticatedScalarResult],
+        blindings: &[AuthenticatedScalarResult],
+        n: usize,
+        fabric: &MpcFabric,
+    ) -> Result<(SharedRangeProof, Vec<AuthenticatedStarkPointOpenResult>), MultiproverError> {
+        let m = values.len();
+        if blindings.len() != m {
+            return Err(MultiproverError::RangeProofError(
+                ProofError::WrongNumBlindingFactors,
+            ));
+        }
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(MultiproverError::RangeProofError(
+                ProofError::InvalidBitsize,
+            ));
+        }
+        if !m.is_power_of_two() {
+            return Err(MultiproverError::RangeProofError(
+                ProofError::InvalidAggregation,
+            ));
+        }
+        if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+            return Err(MultiproverError::RangeProofError(
+                ProofError::InvalidGeneratorsLength,
+            ));
+        }
+
+        let value_bits = decompose_bits(values, n, fabric).await?;
+
+        transcript.rangeproof_domain_sep(n as u64, m as u64);
+
+        // Commit to each value and open the commitments before adding them to the
+        // transcript, so that both parties hold the same transcript state
+        let value_commitments = AuthenticatedStarkPointResult::open_authenticated_batch(
+            &values
+                .iter()
+                .zip(blindings.iter())
+                .map(|(v, v_blinding)| pc_gens.commit_shared(v, v_blinding))
+                .collect::<Vec<_>>(),
+        );
+        for V in value_commitments.iter() {
+            transcript.append_point(b"V", &V.value);
+        }
+
+        // We need 2 + 2nm blinding factors for the commitments A and S; two for the
+        // commitment blinders and nm for each of the s_L, s_R vectors. A further two
+        // are used to blind the commitments to the coefficients of t(x)
+        let nm = n * m;
+        let blinding_factors = fabric.random_shared_scalars_authenticated(4 + 2 * nm);
+        let (a_blinding, s_blinding) = (blinding_factors[0].clone(), blinding_factors[1].clone());
+        let s_L = blinding_factors[2..2 + nm].to_vec();
+        let s_R = blinding_factors[2 + nm..2 + 2 * nm].to_vec();
+        let (t_1_blinding, t_2_blinding) = (
+            blinding_factors[2 + 2 * nm].clone(),
+            blinding_factors[3 + 2 * nm].clone(),
+        );
+
+        // The values are laid out contiguously, value `j` occupies the generators
+        // assigned to party `j` in the single prover aggregation protocol
+        let one = fabric.one();
+        let a_L: Vec<AuthenticatedScalarResult> = value_bits
+            .iter()
+            .flat_map(|bits| bits.iter().cloned())
+            .collect();
+        let a_R: Vec<AuthenticatedScalarResult> = a_L.iter().map(|a_L_i| a_L_i - &one).collect();
+
+        // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding
+        let A = StarkPoint::msm_authenticated_iter(
+            iter::once(&a_blinding)
+                .chain(a_L.iter())
+                .chain(a_R.iter())
+                .cloned(),
+            iter::once(pc_gens.B_blinding)
+                .chain(bp_gens.G(n, m).copied())
+                .chain(bp_gens.H(n, m).copied()),
+        );
+
+        // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
+        let S = StarkPoint::msm_authenticated_iter(
+            iter::once(&s_blinding)
+                .chain(s_L.iter())
+                .chain(s_R.iter())
+                .cloned(),
+            iter::once(pc_gens.B_blinding)
+                .chain(bp_gens.G(n, m).copied())
+                .chain(bp_gens.H(n, m).copied()),
+        );
+
+        let (A, S) = {
+            let mut opened_values =
+                AuthenticatedStarkPointResult::open_authenticated_batch(&[A, S]);
+            (opened_values.remove(0), opened_values.remove(0))
+        };
+
+        transcript.append_point(b"A", &A.value);
+        transcript.append_point(b"S", &S.value);
+
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        let zz = &z * &z;
+
+        // Construct the vector polynomials
+        //      l(x) = (a_L - z) + s_L * x
+        //      r(x) = y^nm o (a_R + z + s_R * x) + z^2 * (z^0 * 2^n || ... || z^(m-1) * 2^n)
+        let exp_y = util::exp_iter_result(y.clone(), nm, fabric);
+        let exp_z = util::exp_iter_result(z.clone(), m, fabric);
+        let powers_of_2 =
+            util::exp_iter_result(fabric.allocate_scalar(Scalar::from(2u64)), n, fabric);
+
+        let mut l_0 = Vec::with_capacity(nm);
+        let mut l_1 = Vec::with_capacity(nm);
+        let mut r_0 = Vec::with_capacity(nm);
+        let mut r_1 = Vec::with_capacity(nm);
+        for i in 0..nm {
+            let (j, k) = (i / n, i % n);
+            let zz_z_j_2_k = &zz * &exp_z[j] * &powers_of_2[k];
+
+            l_0.push(&a_L[i] - &z);
+            l_1.push(s_L[i].clone());
+            r_0.push(&exp_y[i] * (&a_R[i] + &z) + zz_z_j_2_k);
+            r_1.push(&exp_y[i] * &s_R[i]);
+        }
+
+        // Only the linear and quadratic coefficients of t(x) = <l(x), r(x)> are committed
+        // to; the evaluation t(x) is computed directly from l(x), r(x) below
+        let t_1 = authenticated_inner_product(&l_0, &r_1, fabric.clone())
+            + authenticated_inner_product(&l_1, &r_0, fabric.clone());
+        let t_2 = authenticated_inner_product(&l_1, &r_1, fabric.clone());
+
+        let (T_1, T_2) = {
+            let mut opened_values = AuthenticatedStarkPointResult::open_authenticated_batch(&[
+                pc_gens.commit_shared(&t_1, &t_1_blinding),
+                pc_gens.commit_shared(&t_2, &t_2_blinding),
+            ]);
+            (opened_values.remove(0), opened_values.remove(0))
+        };
+
+        transcript.append_point(b"T_1", &T_1.value);
+        transcript.append_point(b"T_2", &T_2.value);
+
+        let x = transcript.challenge_scalar(b"x");
+
+        // Evaluate l(x), r(x) and the blinding factors at the challenge point
+        let l_vec: Vec<AuthenticatedScalarResult> = l_0
+            .iter()
+            .zip(l_1.iter())
+            .map(|(l_0_i, l_1_i)| l_0_i + &x * l_1_i)
+            .collect();
+        let r_vec: Vec<AuthenticatedScalarResult> = r_0
+            .iter()
+            .zip(r_1.iter())
+            .map(|(r_0_i, r_1_i)| r_0_i + &x * r_1_i)
+            .collect();
+
+        let t_x = authenticated_inner_product(&l_vec, &r_vec, fabric.clone());
+        let v_blinding_term: AuthenticatedScalarResult = blindings
+            .iter()
+            .zip(exp_z.iter())
+            .map(|(v_blinding, z_j)| &zz * z_j * v_blinding)
+            .sum();
+        let t_x_blinding = v_blinding_term + &x * (t_1_blinding + &x * t_2_blinding);
+        let e_blinding = a_blinding + &x * s_blinding;
+
+        // Open the final set of transcript values
+        let (t_x, t_x_blinding, e_blinding) = {
+            let mut opened_values = AuthenticatedScalarResult::open_authenticated_batch(&[
+                t_x,
+                t_x_blinding,
+                e_blinding,
+            ]);
+
+            (
+                opened_values.remove(0),
+                opened_values.remove(0),
+                opened_values.remove(0),
+            )
+        };
+
+        transcript.append_scalar(b"t_x", &t_x.value);
+        transcript.append_scalar(b"t_x_blinding", &t_x_blinding.value);
+        transcript.append_scalar(b"e_blinding", &e_blinding.value);
+
+        // Get a challenge value to combine statements for the IPP
+        let w = transcript.challenge_scalar(b"w");
+        let Q = w * pc_gens.B;
+
+        let G_factors: Vec<ScalarResult> = iter::repeat(fabric.one()).take(nm).collect();
+        let H_factors: Vec<ScalarResult> = util::exp_iter_result(y.inverse(), nm, fabric);
+
+        let ipp_proof = SharedInnerProductProof::create(
+            transcript,
+            Q,
+            &G_factors,
+            &H_factors,
+            bp_gens.G(n, m).copied().collect(),
+            bp_gens.H(n, m).copied().collect(),
+            l_vec,
+            r_vec,
+            fabric,
+        )?;
+
+        Ok((
+            SharedRangeProof {
+                A,
+                S,
+                T_1,
+                T_2,
+                t_x,
+                t_x_blinding,
+                e_blinding,
+                ipp_proof,
+                party_id: fabric.party_id(),
+            },
+            value_commitments,
+        ))
+    }
+
+    /// Opens a shared proof
+    ///
+    /// Only the inner product proof must be opened; every other value is opened
+    /// during the course of proof generation to keep the transcripts consistent, so
+    /// only the MACs of those openings are checked here. A failed check is attributed
+    /// to the step of the protocol at which the inconsistent shares were sent
+    ///
+    /// The resulting type is `RangeProof` as the values are no longer secret shared
+    pub async fn open(&self) -> Result<RangeProof, MultiproverError> {
+        let A = self.check_point(&self.A, ProofStep::BitCommitments).await?;
+        let S = self.check_point(&self.S, ProofStep::BitCommitments).await?;
+        let T_1 = self
+            .check_point(&self.T_1, ProofStep::PolynomialCommitments)
+            .await?;
+        let T_2 = self
+            .check_point(&self.T_2, ProofStep::PolynomialCommitments)
+            .await?;
+
+        let t_x = self.check_scalar(&self.t_x).await?;
+        let t_x_blinding = self.check_scalar(&self.t_x_blinding).await?;
+        let e_blinding = self.check_scalar(&self.e_blinding).await?;
+
+        let ipp_proof = self.ipp_proof.open().await?;
+
+        Ok(RangeProof {
+            A,
+            S,
+            T_1,
+            T_2,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+
+    /// Await the MAC check of a point opened at `step`
+    async fn check_point(
+        &self,
+        opening: &AuthenticatedStarkPointOpenResult,
+        step: ProofStep,
+    ) -> Result<StarkPoint, MultiproverError> {
+        opening
+            .clone()
+            .await
+            .map_err(|err: MpcError| MultiproverError::from_opening(err, step, self.party_id))
+    }
+
+    /// Await the MAC check of one of the scalars opened with the evaluation of \\(t(x)\\)
+    async fn check_scalar(
+        &self,
+        opening: &AuthenticatedScalarOpenResult,
+    ) -> Result<Scalar, MultiproverError> {
+        opening.clone().await.map_err(|err| {
+            MultiproverError::from_opening(err, ProofStep::PolynomialEvaluation, self.party_id)
+        })
+    }
+}
+
+/// The number of random bits beyond the range with which a value is masked before it
+/// is opened for bit decomposition
+const MASK_BITS: usize = 128;
+
+/// Decompose each of the shared `values` into its `n` low-order bits, little-endian
+///
+/// Each value `v` is masked by a shared random value `r` of `n + MASK_BITS` bits and
+/// `c = v + r` is opened; for `v` in range the sum does not wrap around the modulus, so
+/// the low-order bits of `v` are those of `c - r`. These are computed by subtracting
+/// the shared bits of `r` from the public bits of `c`, propagating the borrow with one
+/// multiplication per bit.
+async fn decompose_bits(
+    values: &[AuthenticatedScalarResult],
+    n: usize,
+    fabric: &MpcFabric,
+) -> Result<Vec<Vec<AuthenticatedScalarResult>>, MultiproverError> {
+    let powers_of_2 = util::exp_iter_result(
+        fabric.allocate_scalar(Scalar::from(2u64)),
+        n + MASK_BITS,
+        fabric,
+    );
+    let mask_bits = values
+        .iter()
+        .map(|_| fabric.random_shared_bits(n + MASK_BITS))
+        .collect::<Vec<_>>();
+    let masked_values = values
+        .iter()
+        .zip(mask_bits.iter())
+        .map(|(value, bits)| {
+            let mask: AuthenticatedScalarResult = bits
+                .iter()
+                .zip(powers_of_2.iter())
+                .map(|(bit, power)| power * bit)
+                .sum();
+            value + mask
+        })
+        .collect::<Vec<_>>();
+
+    let mut value_bits = Vec::with_capacity(values.len());
+    let one = fabric.one_authenticated();
+    for (opening, mask_bits) in AuthenticatedScalarResult::open_authenticated_batch(&masked_values)
+        .into_iter()
+        .zip(mask_bits.iter())
+    {
+        let masked_value = opening.await.map_err(|err| {
+            MultiproverError::from_opening(err, ProofStep::BitDecomposition, fabric.party_id())
+        })?;
+        let masked_bytes = masked_value.to_bytes_be();
+
+        // A borrow of `None` is known to be zero
+        let mut bits = Vec::with_capacity(n);
+        let mut borrow: Option<AuthenticatedScalarResult> = None;
+        for (i, r_i) in mask_bits.iter().take(n).enumerate() {
+            let c_i = (masked_bytes[masked_bytes.len() - 1 - i / 8] >> (i % 8)) & 1 == 1;
+
+            // The difference bit is c_i ^ r_i ^ b_i, the borrow out is set if
+            // r_i + b_i exceeds c_i
+            let (r_xor_b, r_or_b, r_and_b) = match borrow {
+                Some(b_i) => {
+                    let r_and_b = r_i * &b_i;
+                    let r_or_b = r_i + &b_i - &r_and_b;
+                    (&r_or_b - &r_and_b, Some(r_or_b), Some(r_and_b))
+                }
+                None => (r_i.clone(), Some(r_i.clone()), None),
+            };
+
+            if c_i {
+                bits.push(&one - &r_xor_b);
+                borrow = r_and_b;
+            } else {
+                bits.push(r_xor_b);
+                borrow = r_or_b;
+            }
+        }
+
+        value_bits.push(bits);
+    }
+
+    Ok(value_bits)
+}
@@ -0,0 +1,66 @@
+//! Estimates of the preprocessing consumed by a collaborative proof
+//!
+//! The collaborative prover draws Beaver triples and shared random values from the
+//! fabric's `SharedValueSource`. When this source is backed by an offline preprocessing
+//! phase, the preprocessing must be provisioned before the proof is generated; the counts
+//! below are exact for the current implementation of `MpcProver::prove`.
+
+/// The preprocessing consumed by `MpcProver::prove` and `PartiallySharedR1CSProof::open`
+///
+/// Preprocessing consumed while building the circuit, e.g. the triples used to compute
+/// the outputs of phase one multiplication gates, is not included; it has already been
+/// drawn from the fabric by the time the proof is generated.
+///
+/// The estimate covers `PartiallySharedR1CSProof::open` only. Opening a proof to a
+/// single party with `open_to` additionally has that party share one random mask for
+/// each of the inner product scalars `a` and `b`; these masks are not counted.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PreprocessingCost {
+    /// The number of Beaver triples consumed
+    pub triples: usize,
+    /// The number of shared random values consumed
+    pub shared_randoms: usize,
+    /// The number of shared inverse pairs consumed
+    pub inverse_pairs: usize,
+    /// The number of sequential rounds in which proof values are opened
+    ///
+    /// This does not include the openings made by Beaver multiplications, which are
+    /// batched by the fabric alongside the rounds counted here
+    pub opening_rounds: usize,
+}
+
+impl PreprocessingCost {
+    /// Compute the cost of proving a circuit with `n1` multipliers allocated in the first
+    /// phase, `n2` multipliers allocated by randomized constraints, and `m` commitments
+    pub fn new(n1: usize, n2: usize, m: usize) -> Self {
+        let n = n1 + n2;
+        let padded_n = n.next_power_of_two();
+        let has_2nd_phase_commitments = n2 > 0;
+
+        // Triples are consumed by:
+        //  - The multiplication gates allocated in the second phase, one each
+        //  - The nine inner products over the coefficients of l(x) and r(x) that form t(x)
+        //  - The blinding factor of t_2, one per commitment
+        //  - The cross terms c_L and c_R of each IPP round, whose lengths sum to padded_n - 1
+        let triples = n2 + 9 * n + m + 2 * (padded_n - 1);
+
+        // The blinding factors of A_I, A_O, S and the vectors s_L, s_R in each phase, plus
+        // the blinding factors of T_1, T_3, T_4, T_5, T_6
+        let mut shared_randoms = 3 + 2 * n1 + 5;
+        if has_2nd_phase_commitments {
+            shared_randoms += 3 + 2 * n2;
+        }
+
+        // The phase one commitments, the phase two commitments (if any), T_i, the evaluation
+        // of t(x) and its blinding factors, one round per IPP round, and the final IPP scalars
+        let ipp_rounds = padded_n.trailing_zeros() as usize;
+        let opening_rounds = 4 + usize::from(has_2nd_phase_commitments) + ipp_rounds;
+
+        Self {
+            triples,
+            shared_randoms,
+            inverse_pairs: 0,
+            opening_rounds,
+        }
+    }
+}
@@ -1,10 +1,14 @@
 #![allow(non_snake_case)]
 //! Definition of the proof struct.
 
+use mpc_stark::algebra::authenticated_scalar::AuthenticatedScalarOpenResult;
+use mpc_stark::algebra::authenticated_stark_point::AuthenticatedStarkPointOpenResult;
 use mpc_stark::algebra::scalar::ScalarResult;
 use mpc_stark::algebra::stark_curve::StarkPointResult;
+use mpc_stark::network::PartyId;
 
-use crate::errors::MultiproverError;
+use crate::errors::{MultiproverError, ProofStep};
+use crate::inner_product_proof::InnerProductProof;
 use crate::r1cs::R1CSProof;
 
 use super::mpc_inner_product::SharedInnerProductProof;
@@ -52,6 +56,14 @@ pub struct PartiallySharedR1CSProof {
     pub(super) ipp_proof: SharedInnerProductProof,
     #[cfg(feature = "integration_test")]
     pub ipp_proof: SharedInnerProductProof,
+    /// The authenticated openings of the points sent during proof generation, tagged
+    /// with the step at which they were sent
+    pub(super) point_openings: Vec<(ProofStep, AuthenticatedStarkPointOpenResult)>,
+    /// The authenticated openings of the scalars sent during proof generation, tagged
+    /// with the step at which they were sent
+    pub(super) scalar_openings: Vec<(ProofStep, AuthenticatedScalarOpenResult)>,
+    /// The ID of the local party, used to attribute failed openings
+    pub(super) party_id: PartyId,
 }
 
 impl PartiallySharedR1CSProof {
@@ -59,10 +71,55 @@ impl PartiallySharedR1CSProof {
     pub async fn open(&self) -> Result<R1CSProof, MultiproverError> {
         // To open, only the inner product proof must be opened
         // Every other value is opened during the course of proof generation to maintain
-        // a consistent Merlin transcript
+        // a consistent Merlin transcript, so we need only check the MACs of those openings
+        self.check_openings().await?;
         let ipp_open = self.ipp_proof.open().await?;
 
-        Ok(R1CSProof {
+        Ok(self.build_proof(ipp_open).await)
+    }
+
+    /// Opens the proof to `party` only, generating a standard R1CS Proof on that party
+    ///
+    /// The final scalars `a` and `b` of the inner product proof are opened privately to
+    /// `party` after their MACs are checked; without them the other parties cannot
+    /// assemble the proof. Returns `None` on every other party.
+    ///
+    /// Note that `t_x`, `t_x_blinding` and `e_blinding` are absorbed by the Fiat-Shamir
+    /// transcript before the inner product proof is generated, so every prover must learn
+    /// them during proof generation; they cannot be withheld.
+    pub async fn open_to(&self, party: PartyId) -> Result<Option<R1CSProof>, MultiproverError> {
+        self.check_openings().await?;
+        let ipp_open = match self.ipp_proof.open_to(party).await? {
+            Some(ipp_open) => ipp_open,
+            None => return Ok(None),
+        };
+
+        Ok(Some(self.build_proof(ipp_open).await))
+    }
+
+    /// Await the MAC checks of the values opened during proof generation, in the order
+    /// in which they were sent
+    async fn check_openings(&self) -> Result<(), MultiproverError> {
+        for (step, opening) in self.point_openings.iter() {
+            opening
+                .clone()
+                .await
+                .map_err(|err| MultiproverError::from_opening(err, *step, self.party_id))?;
+        }
+
+        for (step, opening) in self.scalar_openings.iter() {
+            opening
+                .clone()
+                .await
+                .map_err(|err| MultiproverError::from_opening(err, *step, self.party_id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Assemble a standard R1CS proof from the opened inner product proof
+    async fn build_proof(&self, ipp_proof: InnerProductProof) -> R1CSProof {
+        R1CSProof {
             A_I1: self.A_I1.clone().await,
             A_O1: self.A_O1.clone().await,
             S1: self.S1.clone().await,
@@ -77,7 +134,8 @@ impl PartiallySharedR1CSProof {
             t_x: self.t_x.clone().await,
             t_x_blinding: self.t_x_blinding.clone().await,
             e_blinding: self.e_blinding.clone().await,
-            ipp_proof: ipp_open,
-        })
+            ipp_proof,
+            fingerprint: None,
+        }
     }
 }
@@ -0,0 +1,339 @@
+//! The `dealer` module contains the API for the dealer state while the dealer is
+//! engaging in an aggregated multiparty computation protocol.
+//!
+//! Each state of the MPC protocol is represented by a different Rust
+//! type.  The state transitions consume the previous state, making it
+//! a compile error to perform the steps out of order or to repeat a
+//! step.
+//!
+//! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
+//! [the API for the aggregated multiparty computation protocol](../index.html).
+
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+
+use merlin::HashChainTranscript as Transcript;
+use mpc_stark::algebra::scalar::Scalar;
+use mpc_stark::algebra::stark_curve::StarkPoint;
+
+use crate::errors::MPCError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::inner_product_proof::InnerProductProof;
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+use super::messages::*;
+use super::RangeProof;
+
+/// Used to construct a dealer for the aggregated rangeproof MPC protocol.
+pub struct Dealer {}
+
+impl Dealer {
+    /// Creates a new dealer coordinating `m` parties proving `n`-bit ranges.
+    pub fn new<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: usize,
+        m: usize,
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(MPCError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() {
+            return Err(MPCError::InvalidAggregation);
+        }
+        if bp_gens.gens_capacity < n {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+        if bp_gens.party_capacity < m {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+
+        transcript.rangeproof_domain_sep(n as u64, m as u64);
+
+        Ok(DealerAwaitingBitCommitments {
+            bp_gens,
+            pc_gens,
+            transcript,
+            n,
+            m,
+        })
+    }
+}
+
+/// A dealer waiting for the parties to send their [`BitCommitment`]s.
+pub struct DealerAwaitingBitCommitments<'a, 'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    n: usize,
+    m: usize,
+}
+
+impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
+    /// Receive each party's [`BitCommitment`]s and compute the [`BitChallenge`].
+    pub fn receive_bit_commitments(
+        self,
+        bit_commitments: Vec<BitCommitment>,
+    ) -> Result<(DealerAwaitingPolyCommitments<'a, 'b>, BitChallenge), MPCError> {
+        if self.m != bit_commitments.len() {
+            return Err(MPCError::WrongNumBitCommitments);
+        }
+
+        // Commit each V_j individually
+        for vc in bit_commitments.iter() {
+            self.transcript.append_point(b"V", &vc.V_j);
+        }
+
+        // Commit aggregated A_j, S_j
+        let A = bit_commitments
+            .iter()
+            .fold(StarkPoint::identity(), |acc, vc| acc + vc.A_j);
+        self.transcript.append_point(b"A", &A);
+
+        let S = bit_commitments
+            .iter()
+            .fold(StarkPoint::identity(), |acc, vc| acc + vc.S_j);
+        self.transcript.append_point(b"S", &S);
+
+        let y = self.transcript.challenge_scalar(b"y");
+        let z = self.transcript.challenge_scalar(b"z");
+        let bit_challenge = BitChallenge { y, z };
+
+        Ok((
+            DealerAwaitingPolyCommitments {
+                n: self.n,
+                m: self.m,
+                transcript: self.transcript,
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                bit_challenge,
+                bit_commitments,
+                A,
+                S,
+            },
+            bit_challenge,
+        ))
+    }
+}
+
+/// A dealer which has sent the [`BitChallenge`] to the parties and
+/// is waiting for their [`PolyCommitment`]s.
+pub struct DealerAwaitingPolyCommitments<'a, 'b> {
+    n: usize,
+    m: usize,
+    transcript: &'a mut Transcript,
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    bit_challenge: BitChallenge,
+    bit_commitments: Vec<BitCommitment>,
+    /// Aggregated commitment to the parties' bits
+    A: StarkPoint,
+    /// Aggregated commitment to the parties' bit blindings
+    S: StarkPoint,
+}
+
+impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
+    /// Receive [`PolyCommitment`]s from the parties and compute the
+    /// [`PolyChallenge`].
+    pub fn receive_poly_commitments(
+        self,
+        poly_commitments: Vec<PolyCommitment>,
+    ) -> Result<(DealerAwaitingProofShares<'a, 'b>, PolyChallenge), MPCError> {
+        if self.m != poly_commitments.len() {
+            return Err(MPCError::WrongNumPolyCommitments);
+        }
+
+        // Commit sums of T_1_j's and T_2_j's
+        let T_1 = poly_commitments
+            .iter()
+            .fold(StarkPoint::identity(), |acc, pc| acc + pc.T_1_j);
+        let T_2 = poly_commitments
+            .iter()
+            .fold(StarkPoint::identity(), |acc, pc| acc + pc.T_2_j);
+
+        self.transcript.append_point(b"T_1", &T_1);
+        self.transcript.append_point(b"T_2", &T_2);
+
+        let x = self.transcript.challenge_scalar(b"x");
+        let poly_challenge = PolyChallenge { x };
+
+        Ok((
+            DealerAwaitingProofShares {
+                n: self.n,
+                m: self.m,
+                transcript: self.transcript,
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                bit_challenge: self.bit_challenge,
+                bit_commitments: self.bit_commitments,
+                A: self.A,
+                S: self.S,
+                poly_challenge,
+                poly_commitments,
+                T_1,
+                T_2,
+            },
+            poly_challenge,
+        ))
+    }
+}
+
+/// A dealer which has sent the [`PolyChallenge`] to the parties and
+/// is waiting to aggregate their [`ProofShare`]s into a
+/// [`RangeProof`].
+pub struct DealerAwaitingProofShares<'a, 'b> {
+    n: usize,
+    m: usize,
+    transcript: &'a mut Transcript,
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    bit_challenge: BitChallenge,
+    bit_commitments: Vec<BitCommitment>,
+    poly_challenge: PolyChallenge,
+    poly_commitments: Vec<PolyCommitment>,
+    A: StarkPoint,
+    S: StarkPoint,
+    T_1: StarkPoint,
+    T_2: StarkPoint,
+}
+
+impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
+    /// Assembles proof shares into an `RangeProof`.
+    ///
+    /// Used as a helper function by `receive_trusted_shares` (which
+    /// just hands back the result) and `receive_shares` (which
+    /// validates the proof shares first).
+    fn assemble_shares(&mut self, proof_shares: &[ProofShare]) -> Result<RangeProof, MPCError> {
+        if self.m != proof_shares.len() {
+            return Err(MPCError::WrongNumProofShares);
+        }
+
+        // Validate lengths for each share
+        let bad_shares: Vec<usize> = proof_shares
+            .iter()
+            .enumerate()
+            .filter(|(j, share)| share.check_size(self.n, self.bp_gens, *j).is_err())
+            .map(|(j, _)| j)
+            .collect();
+
+        if !bad_shares.is_empty() {
+            return Err(MPCError::MalformedProofShares { bad_shares });
+        }
+
+        let t_x: Scalar = proof_shares.iter().map(|ps| ps.t_x).sum();
+        let t_x_blinding: Scalar = proof_shares.iter().map(|ps| ps.t_x_blinding).sum();
+        let e_blinding: Scalar = proof_shares.iter().map(|ps| ps.e_blinding).sum();
+
+        self.transcript.append_scalar(b"t_x", &t_x);
+        self.transcript
+            .append_scalar(b"t_x_blinding", &t_x_blinding);
+        self.transcript.append_scalar(b"e_blinding", &e_blinding);
+
+        // Get a challenge value to combine statements for the IPP
+        let w = self.transcript.challenge_scalar(b"w");
+        let Q = self.pc_gens.B * w;
+
+        let nm = self.n * self.m;
+        let G_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(nm).collect();
+        let H_factors: Vec<Scalar> = util::exp_iter(self.bit_challenge.y.inverse())
+            .take(nm)
+            .collect();
+
+        let l_vec: Vec<Scalar> = proof_shares
+            .iter()
+            .flat_map(|ps| ps.l_vec.iter().copied())
+            .collect();
+        let r_vec: Vec<Scalar> = proof_shares
+            .iter()
+            .flat_map(|ps| ps.r_vec.iter().copied())
+            .collect();
+
+        let ipp_proof = InnerProductProof::create(
+            self.transcript,
+            &Q,
+            &G_factors,
+            &H_factors,
+            self.bp_gens.G(self.n, self.m).cloned().collect(),
+            self.bp_gens.H(self.n, self.m).cloned().collect(),
+            l_vec,
+            r_vec,
+        );
+
+        Ok(RangeProof {
+            A: self.A,
+            S: self.S,
+            T_1: self.T_1,
+            T_2: self.T_2,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+
+    /// Audit the given `proof_shares`, then assemble them into the final
+    /// aggregated [`RangeProof`].
+    ///
+    /// Each share is checked against the commitments its party sent
+    /// earlier in the protocol, so that a malformed share is attributed
+    /// to the party that produced it.  If any share is malformed, returns
+    /// [`MPCError::MalformedProofShares`] with the indices of all parties
+    /// whose shares failed the audit.
+    pub fn receive_shares(mut self, proof_shares: &[ProofShare]) -> Result<RangeProof, MPCError> {
+        if self.m != proof_shares.len() {
+            return Err(MPCError::WrongNumProofShares);
+        }
+
+        let bad_shares: Vec<usize> = proof_shares
+            .iter()
+            .enumerate()
+            .filter(|(j, share)| {
+                share
+                    .audit_share(
+                        self.bp_gens,
+                        self.pc_gens,
+                        *j,
+                        &self.bit_commitments[*j],
+                        &self.bit_challenge,
+                        &self.poly_commitments[*j],
+                        &self.poly_challenge,
+                    )
+                    .is_err()
+            })
+            .map(|(j, _)| j)
+            .collect();
+
+        if !bad_shares.is_empty() {
+            return Err(MPCError::MalformedProofShares { bad_shares });
+        }
+
+        self.assemble_shares(proof_shares)
+    }
+
+    /// Assemble the final aggregated [`RangeProof`] from the given
+    /// `proof_shares`, but skip validation of the proof.
+    ///
+    /// ## WARNING
+    ///
+    /// This function does **NOT** validate the proof shares.  It is
+    /// suitable for creating aggregated proofs when all parties are
+    /// known by the dealer to be honest (for instance, when there's
+    /// only one party playing all roles).
+    ///
+    /// Otherwise, use
+    /// [`receive_shares`](DealerAwaitingProofShares::receive_shares),
+    /// which validates that all shares are well-formed, or else
+    /// detects which party(ies) submitted malformed shares.
+    pub fn receive_trusted_shares(
+        mut self,
+        proof_shares: &[ProofShare],
+    ) -> Result<RangeProof, MPCError> {
+        self.assemble_shares(proof_shares)
+    }
+}
@@ -0,0 +1,811 @@
+#![allow(non_snake_case)]
+#![doc = include_str!("../../docs/range-proof-protocol.md")]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+
+use merlin::HashChainTranscript as Transcript;
+use mpc_stark::algebra::scalar::{Scalar, SCALAR_BYTES};
+use mpc_stark::algebra::stark_curve::{StarkPoint, STARK_POINT_BYTES};
+use rand::{thread_rng, CryptoRng, RngCore};
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::inner_product_proof::InnerProductProof;
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+pub mod dealer;
+pub mod messages;
+pub mod party;
+
+/// The `RangeProof` struct represents a proof that one or more values
+/// are in a range.
+///
+/// The `RangeProof` struct contains functions for creating and
+/// verifying aggregated range proofs.  The single-value case is
+/// implemented as a special case of aggregated range proofs.
+///
+/// The bitsize of the range, as well as the list of commitments to
+/// the values, are not included in the proof, and must be known to
+/// the verifier.
+#[derive(Clone, Debug)]
+pub struct RangeProof {
+    /// Commitment to the bits of the value
+    pub(crate) A: StarkPoint,
+    /// Commitment to the blinding factors
+    pub(crate) S: StarkPoint,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub(crate) T_1: StarkPoint,
+    /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
+    pub(crate) T_2: StarkPoint,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    pub(crate) t_x: Scalar,
+    /// Blinding factor for the synthetic commitment to \\(t(x)\\)
+    pub(crate) t_x_blinding: Scalar,
+    /// Blinding factor for the synthetic commitment to the inner-product arguments
+    pub(crate) e_blinding: Scalar,
+    /// Proof data for the inner-product argument.
+    pub(crate) ipp_proof: InnerProductProof,
+}
+
+impl RangeProof {
+    /// Create a rangeproof for a given pair of value `v` and
+    /// blinding scalar `v_blinding`.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple`].
+    pub fn prove_single_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, StarkPoint), ProofError> {
+        let (p, Vs) = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v],
+            &[*v_blinding],
+            n,
+            rng,
+        )?;
+        Ok((p, Vs[0]))
+    }
+
+    /// Create a rangeproof for a given pair of value `v` and
+    /// blinding scalar `v_blinding`.
+    /// This is a convenience wrapper around [`RangeProof::prove_single_with_rng`],
+    /// passing in a threadsafe RNG.
+    pub fn prove_single(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(RangeProof, StarkPoint), ProofError> {
+        RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of values.
+    ///
+    /// The proof is created by running the aggregation protocol in
+    /// [`dealer`] and [`party`] locally, with one party per value.
+    ///
+    /// The number of values `m` must be a power of two, and the bitsize `n`
+    /// must be one of 8, 16, 32, or 64.  The generators must have capacity
+    /// for at least `n` bits and `m` parties.
+    ///
+    /// Returns the proof along with the Pedersen commitments to each value,
+    /// in the order the values were given.
+    pub fn prove_multiple_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<StarkPoint>), ProofError> {
+        use self::dealer::*;
+        use self::party::*;
+
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
+
+        let parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| p.assign_position_with_rng(j, rng))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, rng))
+            .unzip();
+
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let proof = dealer.receive_trusted_shares(&proof_shares)?;
+
+        Ok((proof, value_commitments))
+    }
+
+    /// Create a rangeproof for a set of values.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_with_rng`],
+    /// passing in a threadsafe RNG.
+    pub fn prove_multiple(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<StarkPoint>), ProofError> {
+        RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\).
+    ///
+    /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
+    pub fn verify_single_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &StarkPoint,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &[*V], n, rng)
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\).
+    ///
+    /// This is a convenience wrapper around [`RangeProof::verify_single_with_rng`],
+    /// passing in a threadsafe RNG.
+    pub fn verify_single(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &StarkPoint,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, V, n, &mut thread_rng())
+    }
+
+    /// Verifies an aggregated rangeproof for the given value commitments.
+    pub fn verify_multiple_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[StarkPoint],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let m = value_commitments.len();
+
+        // First, replay the "interactive" protocol using the proof
+        // data to recompute all challenges.
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        transcript.rangeproof_domain_sep(n as u64, m as u64);
+
+        for V in value_commitments.iter() {
+            transcript.append_point(b"V", V);
+        }
+
+        transcript.validate_and_append_point(b"A", &self.A)?;
+        transcript.validate_and_append_point(b"S", &self.S)?;
+
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        let zz = z * z;
+        let minus_z = -z;
+
+        transcript.validate_and_append_point(b"T_1", &self.T_1)?;
+        transcript.validate_and_append_point(b"T_2", &self.T_2)?;
+
+        let x = transcript.challenge_scalar(b"x");
+
+        transcript.append_scalar(b"t_x", &self.t_x);
+        transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &self.e_blinding);
+
+        let w = transcript.challenge_scalar(b"w");
+
+        // Challenge value for batching statements to be verified
+        let c = Scalar::random(rng);
+
+        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(n * m, transcript)?;
+        let s_inv = s.iter().rev();
+
+        let a = self.ipp_proof.a;
+        let b = self.ipp_proof.b;
+
+        // Construct concat_z_and_2, an iterator of the values of
+        // z^0 * \vec(2)^n || z^1 * \vec(2)^n || ... || z^(m-1) * \vec(2)^n
+        let powers_of_2: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).take(n).collect();
+        let concat_z_and_2: Vec<Scalar> = util::exp_iter(z)
+            .take(m)
+            .flat_map(|exp_z| powers_of_2.iter().map(move |exp_2| *exp_2 * exp_z))
+            .collect();
+
+        let g = s.iter().map(|s_i| minus_z - a * *s_i);
+        let h = s_inv
+            .zip(util::exp_iter(y.inverse()))
+            .zip(concat_z_and_2.iter())
+            .map(|((s_i_inv, exp_y_inv), z_and_2)| z + exp_y_inv * (zz * *z_and_2 - b * *s_i_inv));
+
+        let value_commitment_scalars = util::exp_iter(z).take(m).map(|z_exp| c * zz * z_exp);
+        let basepoint_scalar = w * (self.t_x - a * b) + c * (delta(n, m, &y, &z) - self.t_x);
+
+        let mega_check = StarkPoint::msm_iter(
+            iter::once(Scalar::one()) // A
+                .chain(iter::once(x)) // S
+                .chain(iter::once(c * x)) // T_1
+                .chain(iter::once(c * x * x)) // T_2
+                .chain(x_sq.iter().copied()) // L_vec
+                .chain(x_inv_sq.iter().copied()) // R_vec
+                .chain(iter::once(-self.e_blinding - c * self.t_x_blinding)) // B_blinding
+                .chain(iter::once(basepoint_scalar)) // B
+                .chain(g) // G
+                .chain(h) // H
+                .chain(value_commitment_scalars), // V
+            iter::once(self.A)
+                .chain(iter::once(self.S))
+                .chain(iter::once(self.T_1))
+                .chain(iter::once(self.T_2))
+                .chain(self.ipp_proof.L_vec.iter().copied())
+                .chain(self.ipp_proof.R_vec.iter().copied())
+                .chain(iter::once(pc_gens.B_blinding))
+                .chain(iter::once(pc_gens.B))
+                .chain(bp_gens.G(n, m).copied())
+                .chain(bp_gens.H(n, m).copied())
+                .chain(value_commitments.iter().copied()),
+        );
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Verifies an aggregated rangeproof for the given value commitments.
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_with_rng`],
+    /// passing in a threadsafe RNG.
+    pub fn verify_multiple(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[StarkPoint],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Serializes the proof into a byte array of \\(2 \lg n + 4\\) points of
+    /// `STARK_POINT_BYTES` bytes and 5 scalars of `SCALAR_BYTES` bytes, where \\(n\\)
+    /// is the total number of secret bits across the aggregated values.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the range proof encoding is:
+    ///
+    /// * four points \\(A, S, T_1, T_2\\),
+    /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
+    /// * \\(\lg n\\) pairs of points \\(L_0,R_0\dots,L_{\lg n-1},R_{\lg n-1}\\),
+    /// * two scalars \\(a, b\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(&self.A.to_bytes());
+        buf.extend_from_slice(&self.S.to_bytes());
+        buf.extend_from_slice(&self.T_1.to_bytes());
+        buf.extend_from_slice(&self.T_2.to_bytes());
+        buf.extend_from_slice(&self.t_x.to_bytes_be());
+        buf.extend_from_slice(&self.t_x_blinding.to_bytes_be());
+        buf.extend_from_slice(&self.e_blinding.to_bytes_be());
+        buf.extend(self.ipp_proof.to_bytes_iter());
+        buf
+    }
+
+    /// Returns the size in bytes required to serialize the `RangeProof`.
+    pub fn serialized_size(&self) -> usize {
+        4 * STARK_POINT_BYTES + 3 * SCALAR_BYTES + self.ipp_proof.serialized_size()
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `RangeProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        if slice.len() < 4 * STARK_POINT_BYTES + 3 * SCALAR_BYTES {
+            return Err(ProofError::FormatError);
+        }
+        let mut slice = slice;
+
+        // This macro takes care of counting bytes in the slice
+        macro_rules! read_point {
+            () => {{
+                let tmp = util::read_exact::<STARK_POINT_BYTES>(slice);
+                slice = &slice[STARK_POINT_BYTES..];
+                StarkPoint::from_bytes(&tmp).map_err(|_| ProofError::FormatError)
+            }};
+        }
+
+        macro_rules! read_scalar {
+            () => {{
+                let tmp = util::read_exact::<SCALAR_BYTES>(slice);
+                slice = &slice[SCALAR_BYTES..];
+                Scalar::from_be_bytes_mod_order(&tmp)
+            }};
+        }
+
+        let A = read_point!()?;
+        let S = read_point!()?;
+        let T_1 = read_point!()?;
+        let T_2 = read_point!()?;
+        let t_x = read_scalar!();
+        let t_x_blinding = read_scalar!();
+        let e_blinding = read_scalar!();
+
+        let ipp_proof = InnerProductProof::from_bytes(slice)?;
+
+        Ok(RangeProof {
+            A,
+            S,
+            T_1,
+            T_2,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+}
+
+impl Serialize for RangeProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for RangeProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RangeProofVisitor;
+
+        impl<'de> Visitor<'de> for RangeProofVisitor {
+            type Value = RangeProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("a valid RangeProof")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(100 /* default */));
+                while let Ok(Some(next_byte)) = seq.next_element::<u8>() {
+                    bytes.push(next_byte)
+                }
+
+                RangeProof::from_bytes(&bytes).map_err(serde::de::Error::custom)
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<RangeProof, E>
+            where
+                E: serde::de::Error,
+            {
+                RangeProof::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(RangeProofVisitor)
+    }
+}
+
+/// Compute
+/// \\[
+/// \delta(y,z) = (z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{n \cdot m} \rangle - \sum_{j=0}^{m-1} z^{j+3} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n \cdot m} \rangle
+/// \\]
+fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
+    let sum_y = util::sum_of_powers(y, n * m);
+    let sum_2 = util::sum_of_powers(&Scalar::from(2u64), n);
+    let sum_z = util::sum_of_powers(z, m);
+
+    (*z - *z * *z) * sum_y - *z * *z * *z * sum_2 * sum_z
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn test_delta() {
+        let mut rng = thread_rng();
+        let y = Scalar::random(&mut rng);
+        let z = Scalar::random(&mut rng);
+
+        // Choose n = 256 to ensure we overflow the group order during
+        // the computation, to check that that's done correctly
+        let n = 256;
+
+        // code copied from previous implementation
+        let z2 = z * z;
+        let z3 = z2 * z;
+        let mut power_g = Scalar::zero();
+        let mut exp_y = Scalar::one(); // start at y^0 = 1
+        let mut exp_2 = Scalar::one(); // start at 2^0 = 1
+        for _ in 0..n {
+            power_g = power_g + (z - z2) * exp_y - z3 * exp_2;
+
+            exp_y = exp_y * y; // y^i -> y^(i+1)
+            exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
+        }
+
+        assert_eq!(power_g, delta(n, 1, &y, &z),);
+    }
+
+    /// Given a bitsize `n`, test the following:
+    ///
+    /// 1. Generate `m` random values and create a proof they are all in range;
+    /// 2. Serialize to wire format;
+    /// 3. Deserialize from wire format;
+    /// 4. Verify the proof.
+    fn singleparty_create_and_verify_helper(n: usize, m: usize) {
+        // Split the test into two scopes, so that it's explicit what
+        // data is shared between the prover and the verifier.
+
+        // Both prover and verifier have access to the generators and the proof
+        let max_bitsize = 64;
+        let max_parties = 8;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(max_bitsize, max_parties);
+
+        // Prover's scope
+        let (proof_bytes, value_commitments) = {
+            let mut rng = thread_rng();
+
+            // 0. Create witness data
+            let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
+            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
+            let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+            // 1. Create the proof
+            let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+            let (proof, value_commitments) = RangeProof::prove_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                n,
+            )
+            .unwrap();
+
+            // 2. Return serialized proof and value commitments
+            (bincode::serialize(&proof).unwrap(), value_commitments)
+        };
+
+        // Verifier's scope
+        {
+            // 3. Deserialize
+            let proof: RangeProof = bincode::deserialize(&proof_bytes).unwrap();
+
+            // 4. Verify with the same customization label as above
+            let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+
+            assert!(proof
+                .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_1() {
+        singleparty_create_and_verify_helper(32, 1);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_2() {
+        singleparty_create_and_verify_helper(32, 2);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_1() {
+        singleparty_create_and_verify_helper(64, 1);
+    }
+
+    #[test]
+    fn create_and_verify_n_8_m_4() {
+        singleparty_create_and_verify_helper(8, 4);
+    }
+
+    #[test]
+    fn detect_dishonest_party_during_aggregation() {
+        use self::dealer::*;
+        use self::party::*;
+
+        use crate::errors::MPCError;
+
+        // Simulate four parties, two of which will be dishonest and use a 64-bit value.
+        let m = 4;
+        let n = 32;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let mut rng = thread_rng();
+        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+
+        // Parties 0, 2 are honest and use a 32-bit value
+        let v0 = rng.gen::<u32>() as u64;
+        let v0_blinding = Scalar::random(&mut rng);
+        let party0 = Party::new(&bp_gens, &pc_gens, v0, v0_blinding, n).unwrap();
+
+        let v2 = rng.gen::<u32>() as u64;
+        let v2_blinding = Scalar::random(&mut rng);
+        let party2 = Party::new(&bp_gens, &pc_gens, v2, v2_blinding, n).unwrap();
+
+        // Parties 1, 3 are dishonest and use a 64-bit value
+        let v1 = rng.gen::<u64>();
+        let v1_blinding = Scalar::random(&mut rng);
+        let party1 = Party::new(&bp_gens, &pc_gens, v1, v1_blinding, n).unwrap();
+
+        let v3 = rng.gen::<u64>();
+        let v3_blinding = Scalar::random(&mut rng);
+        let party3 = Party::new(&bp_gens, &pc_gens, v3, v3_blinding, n).unwrap();
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (party0, bit_com0) = party0.assign_position(0).unwrap();
+        let (party1, bit_com1) = party1.assign_position(1).unwrap();
+        let (party2, bit_com2) = party2.assign_position(2).unwrap();
+        let (party3, bit_com3) = party3.assign_position(3).unwrap();
+
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![bit_com0, bit_com1, bit_com2, bit_com3])
+            .unwrap();
+
+        let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
+        let (party1, poly_com1) = party1.apply_challenge(&bit_challenge);
+        let (party2, poly_com2) = party2.apply_challenge(&bit_challenge);
+        let (party3, poly_com3) = party3.apply_challenge(&bit_challenge);
+
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(vec![poly_com0, poly_com1, poly_com2, poly_com3])
+            .unwrap();
+
+        let share0 = party0.apply_challenge(&poly_challenge).unwrap();
+        let share1 = party1.apply_challenge(&poly_challenge).unwrap();
+        let share2 = party2.apply_challenge(&poly_challenge).unwrap();
+        let share3 = party3.apply_challenge(&poly_challenge).unwrap();
+
+        match dealer.receive_shares(&[share0, share1, share2, share3]) {
+            Err(MPCError::MalformedProofShares { bad_shares }) => {
+                assert_eq!(bad_shares, vec![1, 3]);
+            }
+            Err(_) => {
+                panic!("Got wrong error type from malformed shares");
+            }
+            Ok(_) => {
+                panic!("The proof was malformed, but it was not detected");
+            }
+        }
+    }
+
+    #[test]
+    fn detect_dishonest_dealer_during_aggregation() {
+        use self::dealer::*;
+        use self::party::*;
+
+        use crate::errors::MPCError;
+
+        // Simulate one party
+        let m = 1;
+        let n = 32;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let mut rng = thread_rng();
+        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+
+        let v0 = rng.gen::<u32>() as u64;
+        let v0_blinding = Scalar::random(&mut rng);
+        let party0 = Party::new(&bp_gens, &pc_gens, v0, v0_blinding, n).unwrap();
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        // Now do the protocol flow as normal....
+
+        let (party0, bit_com0) = party0.assign_position(0).unwrap();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_com0]).unwrap();
+
+        let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
+
+        let (_dealer, mut poly_challenge) =
+            dealer.receive_poly_commitments(vec![poly_com0]).unwrap();
+
+        // But now simulate a malicious dealer choosing x = 0
+        poly_challenge.x = Scalar::zero();
+
+        let maybe_share0 = party0.apply_challenge(&poly_challenge);
+
+        assert!(maybe_share0.unwrap_err() == MPCError::MaliciousDealer);
+    }
+
+    /// Tests that an honest run of the dealer/party protocol produces a
+    /// proof that verifies, including when the messages are serialized
+    #[test]
+    fn aggregation_with_serialized_messages() {
+        use self::dealer::*;
+        use self::messages::*;
+        use self::party::*;
+
+        let m = 2;
+        let n = 16;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let mut rng = thread_rng();
+        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+
+        let party0 = Party::new(&bp_gens, &pc_gens, 1234, Scalar::random(&mut rng), n).unwrap();
+        let party1 = Party::new(&bp_gens, &pc_gens, 65535, Scalar::random(&mut rng), n).unwrap();
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        // Send a message through bincode, as if over the network
+        fn wire<M: Serialize + serde::de::DeserializeOwned>(msg: M) -> M {
+            bincode::deserialize(&bincode::serialize(&msg).unwrap()).unwrap()
+        }
+
+        let (party0, bit_com0) = party0.assign_position(0).unwrap();
+        let (party1, bit_com1) = party1.assign_position(1).unwrap();
+        let value_commitments = vec![bit_com0.V_j, bit_com1.V_j];
+
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![wire(bit_com0), wire(bit_com1)])
+            .unwrap();
+        let bit_challenge: BitChallenge = wire(bit_challenge);
+
+        let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
+        let (party1, poly_com1) = party1.apply_challenge(&bit_challenge);
+
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(vec![wire(poly_com0), wire(poly_com1)])
+            .unwrap();
+        let poly_challenge: PolyChallenge = wire(poly_challenge);
+
+        let share0: ProofShare = wire(party0.apply_challenge(&poly_challenge).unwrap());
+        let share1: ProofShare = wire(party1.apply_challenge(&poly_challenge).unwrap());
+
+        let proof = dealer.receive_shares(&[share0, share1]).unwrap();
+
+        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+            .is_ok());
+    }
+
+    /// Tests that a proof for a value outside of the range does not verify
+    #[test]
+    fn value_out_of_range_fails() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let blinding = Scalar::random(&mut thread_rng());
+
+        let mut transcript = Transcript::new(b"RangeProofTest");
+        let (proof, value_commitment) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 1 << 8, &blinding, 8)
+                .unwrap();
+
+        let mut transcript = Transcript::new(b"RangeProofTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, 8)
+            .is_err());
+    }
+
+    /// Tests that the byte encoding of a proof round trips
+    #[test]
+    fn serialization_round_trip() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let blinding = Scalar::random(&mut thread_rng());
+
+        let mut transcript = Transcript::new(b"RangeProofTest");
+        let (proof, value_commitment) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1037578891,
+            &blinding,
+            32,
+        )
+        .unwrap();
+
+        let proof_bytes = proof.to_bytes();
+        assert_eq!(proof_bytes.len(), proof.serialized_size());
+        // Four points and 2 lg(32) points in the inner product proof, and five scalars
+        assert_eq!(
+            proof_bytes.len(),
+            (4 + 2 * 5) * STARK_POINT_BYTES + 5 * SCALAR_BYTES
+        );
+
+        let decoded = RangeProof::from_bytes(&proof_bytes).unwrap();
+        let mut transcript = Transcript::new(b"RangeProofTest");
+        assert!(decoded
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, 32)
+            .is_ok());
+    }
+}
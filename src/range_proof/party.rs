@@ -0,0 +1,272 @@
+//! The `party` module contains the API for the party state while the party is
+//! engaging in an aggregated multiparty computation protocol.
+//!
+//! Each state of the MPC protocol is represented by a different Rust
+//! type.  The state transitions consume the previous state, making it
+//! a compile error to perform the steps out of order or to repeat a
+//! step.
+//!
+//! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
+//! [the API for the aggregated multiparty computation protocol](../index.html).
+
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+
+use mpc_stark::algebra::scalar::Scalar;
+use mpc_stark::algebra::stark_curve::StarkPoint;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+use crate::errors::MPCError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::util;
+
+use super::messages::*;
+
+/// Used to construct a party for the aggregated rangeproof MPC protocol.
+pub struct Party {}
+
+impl Party {
+    /// Constructs a `PartyAwaitingPosition` with the given rangeproof parameters.
+    pub fn new<'a>(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: u64,
+        v_blinding: Scalar,
+        n: usize,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(MPCError::InvalidBitsize);
+        }
+        if bp_gens.gens_capacity < n {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+
+        let V = pc_gens.commit(Scalar::from(v), v_blinding);
+
+        Ok(PartyAwaitingPosition {
+            bp_gens,
+            pc_gens,
+            n,
+            v,
+            v_blinding,
+            V,
+        })
+    }
+}
+
+/// A party waiting for the dealer to assign their position in the aggregation.
+pub struct PartyAwaitingPosition<'a> {
+    bp_gens: &'a BulletproofGens,
+    pc_gens: &'a PedersenGens,
+    n: usize,
+    v: u64,
+    v_blinding: Scalar,
+    V: StarkPoint,
+}
+
+impl<'a> PartyAwaitingPosition<'a> {
+    /// Assigns a position in the aggregated proof to this party,
+    /// allowing the party to commit to the bits of their value.
+    pub fn assign_position(
+        self,
+        j: usize,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
+        self.assign_position_with_rng(j, &mut thread_rng())
+    }
+
+    /// Assigns a position in the aggregated proof to this party,
+    /// allowing the party to commit to the bits of their value.
+    pub fn assign_position_with_rng<T: RngCore + CryptoRng>(
+        self,
+        j: usize,
+        rng: &mut T,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
+        if self.bp_gens.party_capacity <= j {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+
+        let bp_share = self.bp_gens.share(j);
+
+        // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding, where
+        // a_L are the bits of the value and a_R = a_L - 1
+        let a_blinding = Scalar::random(rng);
+        let a_L: Vec<Scalar> = (0..self.n)
+            .map(|i| Scalar::from((self.v >> i) & 1))
+            .collect();
+        let A = StarkPoint::msm_iter(
+            iter::once(a_blinding)
+                .chain(a_L.iter().copied())
+                .chain(a_L.iter().map(|a_L_i| *a_L_i - Scalar::one())),
+            iter::once(self.pc_gens.B_blinding)
+                .chain(bp_share.G(self.n).copied())
+                .chain(bp_share.H(self.n).copied()),
+        );
+
+        // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
+        let s_blinding = Scalar::random(rng);
+        let s_L: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(rng)).collect();
+        let s_R: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(rng)).collect();
+        let S = StarkPoint::msm_iter(
+            iter::once(s_blinding)
+                .chain(s_L.iter().copied())
+                .chain(s_R.iter().copied()),
+            iter::once(self.pc_gens.B_blinding)
+                .chain(bp_share.G(self.n).copied())
+                .chain(bp_share.H(self.n).copied()),
+        );
+
+        // Return next state and all commitments
+        let bit_commitment = BitCommitment {
+            V_j: self.V,
+            A_j: A,
+            S_j: S,
+        };
+        let next_state = PartyAwaitingBitChallenge {
+            n: self.n,
+            v: self.v,
+            v_blinding: self.v_blinding,
+            pc_gens: self.pc_gens,
+            j,
+            a_blinding,
+            s_blinding,
+            s_L,
+            s_R,
+        };
+        Ok((next_state, bit_commitment))
+    }
+}
+
+/// A party which has committed to the bits of its value
+/// and is waiting for the aggregated value challenge from the dealer.
+pub struct PartyAwaitingBitChallenge<'a> {
+    n: usize, // bitsize of the range
+    v: u64,
+    v_blinding: Scalar,
+    j: usize,
+    pc_gens: &'a PedersenGens,
+    a_blinding: Scalar,
+    s_blinding: Scalar,
+    s_L: Vec<Scalar>,
+    s_R: Vec<Scalar>,
+}
+
+impl<'a> PartyAwaitingBitChallenge<'a> {
+    /// Receive a [`BitChallenge`] from the dealer and use it to
+    /// compute commitments to the party's polynomial coefficients.
+    pub fn apply_challenge(
+        self,
+        vc: &BitChallenge,
+    ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
+        self.apply_challenge_with_rng(vc, &mut thread_rng())
+    }
+
+    /// Receive a [`BitChallenge`] from the dealer and use it to
+    /// compute commitments to the party's polynomial coefficients.
+    pub fn apply_challenge_with_rng<T: RngCore + CryptoRng>(
+        self,
+        vc: &BitChallenge,
+        rng: &mut T,
+    ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
+        let n = self.n;
+        let offset_y = util::scalar_exp_vartime(&vc.y, (self.j * n) as u64);
+        let offset_z = util::scalar_exp_vartime(&vc.z, self.j as u64);
+
+        // Calculate t by calculating vectors l0, l1, r0, r1 and multiplying
+        let mut l_poly = util::VecPoly1::zero(n);
+        let mut r_poly = util::VecPoly1::zero(n);
+
+        let offset_zz = vc.z * vc.z * offset_z;
+        let mut exp_y = offset_y; // start at y^j
+        let mut exp_2 = Scalar::one(); // start at 2^0 = 1
+        #[allow(clippy::needless_range_loop)]
+        for i in 0..n {
+            let a_L_i = Scalar::from((self.v >> i) & 1);
+            let a_R_i = a_L_i - Scalar::one();
+
+            l_poly.0[i] = a_L_i - vc.z;
+            l_poly.1[i] = self.s_L[i];
+            r_poly.0[i] = exp_y * (a_R_i + vc.z) + offset_zz * exp_2;
+            r_poly.1[i] = exp_y * self.s_R[i];
+
+            exp_y *= vc.y; // y^i -> y^(i+1)
+            exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
+        }
+
+        let t_poly = l_poly.inner_product(&r_poly);
+
+        // Generate x by committing to T_1, T_2 (line 49-54)
+        let t_1_blinding = Scalar::random(rng);
+        let t_2_blinding = Scalar::random(rng);
+        let T_1 = self.pc_gens.commit(t_poly.1, t_1_blinding);
+        let T_2 = self.pc_gens.commit(t_poly.2, t_2_blinding);
+
+        let poly_commitment = PolyCommitment {
+            T_1_j: T_1,
+            T_2_j: T_2,
+        };
+
+        let papc = PartyAwaitingPolyChallenge {
+            v_blinding: self.v_blinding,
+            a_blinding: self.a_blinding,
+            s_blinding: self.s_blinding,
+            offset_zz,
+            l_poly,
+            r_poly,
+            t_poly,
+            t_1_blinding,
+            t_2_blinding,
+        };
+
+        (papc, poly_commitment)
+    }
+}
+
+/// A party which has committed to their polynomial coefficents
+/// and is waiting for the polynomial challenge from the dealer.
+pub struct PartyAwaitingPolyChallenge {
+    offset_zz: Scalar,
+    l_poly: util::VecPoly1,
+    r_poly: util::VecPoly1,
+    t_poly: util::Poly2,
+    v_blinding: Scalar,
+    a_blinding: Scalar,
+    s_blinding: Scalar,
+    t_1_blinding: Scalar,
+    t_2_blinding: Scalar,
+}
+
+impl PartyAwaitingPolyChallenge {
+    /// Receive a [`PolyChallenge`] from the dealer and compute the
+    /// party's proof share.
+    pub fn apply_challenge(self, pc: &PolyChallenge) -> Result<ProofShare, MPCError> {
+        // Prevent a malicious dealer from annihilating the blinding
+        // factors by supplying a zero challenge.
+        if pc.x == Scalar::zero() {
+            return Err(MPCError::MaliciousDealer);
+        }
+
+        let t_blinding_poly = util::Poly2(
+            self.offset_zz * self.v_blinding,
+            self.t_1_blinding,
+            self.t_2_blinding,
+        );
+
+        let t_x = self.t_poly.eval(pc.x);
+        let t_x_blinding = t_blinding_poly.eval(pc.x);
+        let e_blinding = self.a_blinding + self.s_blinding * pc.x;
+        let l_vec = self.l_poly.eval(pc.x);
+        let r_vec = self.r_poly.eval(pc.x);
+
+        Ok(ProofShare {
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            l_vec,
+            r_vec,
+        })
+    }
+}
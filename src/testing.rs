@@ -0,0 +1,289 @@
+//! Helpers for running collaborative proofs inside a single process
+//!
+//! The parties of the MPC communicate over in-memory channels instead of a network
+//! connection, so that collaborative proofs can be generated in unit tests without
+//! binding ports. Each party executes as a task on the current tokio runtime.
+//!
+//! The underlying `MpcFabric` connects exactly two parties, so the simulation always
+//! runs `PARTY0` and `PARTY1`.
+
+use alloc::sync::Arc;
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use futures::{future::join_all, Future, Sink, Stream};
+use merlin::HashChainTranscript as Transcript;
+use mpc_stark::{
+    algebra::{
+        authenticated_stark_point::AuthenticatedStarkPointOpenResult, scalar::Scalar,
+        stark_curve::StarkPoint,
+    },
+    beaver::SharedValueSource,
+    error::{MpcError, MpcNetworkError},
+    network::{MpcNetwork, NetworkOutbound, PartyId},
+    MpcFabric, PARTY0, PARTY1,
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::{
+    errors::MultiproverError, r1cs::R1CSProof, r1cs_mpc::MpcProver, BulletproofGens, PedersenGens,
+};
+
+/// An implementation of a beaver value source that returns
+/// beaver triples (0, 0, 0) for party 0 and (1, 1, 1) for party 1
+///
+/// The values are not random, this source must only be used for testing
+#[derive(Clone, Debug, Default)]
+pub struct PartyIDBeaverSource {
+    /// The ID of the local party
+    party_id: u64,
+}
+
+impl PartyIDBeaverSource {
+    /// Create a new beaver source given the local party_id
+    pub fn new(party_id: u64) -> Self {
+        Self { party_id }
+    }
+}
+
+/// The PartyIDBeaverSource returns beaver triplets split statically between the
+/// parties. We assume a = 2, b = 3 ==> c = 6. [a] = (1, 1); [b] = (3, 0) [c] = (2, 4)
+impl SharedValueSource for PartyIDBeaverSource {
+    fn next_shared_bit(&mut self) -> Scalar {
+        // Simply output partyID, assume partyID \in {0, 1}
+        assert!(self.party_id == 0 || self.party_id == 1);
+        Scalar::from(self.party_id)
+    }
+
+    fn next_triplet(&mut self) -> (Scalar, Scalar, Scalar) {
+        if self.party_id == 0 {
+            (Scalar::from(1u64), Scalar::from(3u64), Scalar::from(2u64))
+        } else {
+            (Scalar::from(1u64), Scalar::from(0u64), Scalar::from(4u64))
+        }
+    }
+
+    fn next_shared_inverse_pair(&mut self) -> (Scalar, Scalar) {
+        (Scalar::from(self.party_id), Scalar::from(self.party_id))
+    }
+
+    fn next_shared_value(&mut self) -> Scalar {
+        Scalar::from(self.party_id)
+    }
+}
+
+/// An unbounded duplex channel used to mock a network connection
+pub struct UnboundedDuplexStream {
+    /// The send side of the stream
+    send: UnboundedSender<NetworkOutbound>,
+    /// The receive side of the stream
+    recv: UnboundedReceiver<NetworkOutbound>,
+}
+
+impl UnboundedDuplexStream {
+    /// Create a new pair of duplex streams
+    pub fn new_duplex_pair() -> (Self, Self) {
+        let (send1, recv1) = unbounded_channel();
+        let (send2, recv2) = unbounded_channel();
+
+        (
+            Self {
+                send: send1,
+                recv: recv2,
+            },
+            Self {
+                send: send2,
+                recv: recv1,
+            },
+        )
+    }
+
+    /// Send a message on the stream
+    pub fn send(&mut self, msg: NetworkOutbound) {
+        self.send.send(msg).unwrap();
+    }
+
+    /// Recv a message from the stream
+    pub async fn recv(&mut self) -> NetworkOutbound {
+        self.recv.recv().await.unwrap()
+    }
+}
+
+/// A network implementation that operates over a duplex channel instead of a
+/// network connection
+pub struct MockNetwork {
+    /// The ID of the local party
+    party_id: PartyId,
+    /// The underlying mock network connection
+    mock_conn: UnboundedDuplexStream,
+}
+
+impl MockNetwork {
+    /// Create a new mock network from one half of a duplex stream
+    pub fn new(party_id: PartyId, stream: UnboundedDuplexStream) -> Self {
+        Self {
+            party_id,
+            mock_conn: stream,
+        }
+    }
+}
+
+#[async_trait]
+impl MpcNetwork for MockNetwork {
+    fn party_id(&self) -> PartyId {
+        self.party_id
+    }
+
+    async fn close(&mut self) -> Result<(), MpcNetworkError> {
+        Ok(())
+    }
+}
+
+impl Stream for MockNetwork {
+    type Item = Result<NetworkOutbound, MpcNetworkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Box::pin(self.mock_conn.recv())
+            .as_mut()
+            .poll(cx)
+            .map(|value| Some(Ok(value)))
+    }
+}
+
+impl Sink<NetworkOutbound> for MockNetwork {
+    type Error = MpcNetworkError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: NetworkOutbound) -> Result<(), Self::Error> {
+        self.mock_conn.send(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Execute `f` as both parties of an in-process MPC, using a `PartyIDBeaverSource`
+///
+/// Exactly two parties are run, there is no way to simulate a larger MPC.
+///
+/// Returns the outputs of `PARTY0` and `PARTY1` respectively
+pub async fn execute_mock_mpc<T, S, F>(f: F) -> (T, T)
+where
+    T: Send + 'static,
+    S: Future<Output = T> + Send + 'static,
+    F: FnMut(MpcFabric) -> S,
+{
+    execute_mock_mpc_with_beaver_source(f, PartyIDBeaverSource::new).await
+}
+
+/// Execute `f` as both parties of an in-process MPC, building each party's beaver
+/// source from its party ID
+///
+/// Exactly two parties are run, there is no way to simulate a larger MPC.
+///
+/// Returns the outputs of `PARTY0` and `PARTY1` respectively
+pub async fn execute_mock_mpc_with_beaver_source<T, S, F, B, BS>(
+    mut f: F,
+    mut beaver_source: B,
+) -> (T, T)
+where
+    T: Send + 'static,
+    S: Future<Output = T> + Send + 'static,
+    F: FnMut(MpcFabric) -> S,
+    B: FnMut(PartyId) -> BS,
+    BS: 'static + SharedValueSource,
+{
+    // Build a duplex stream to broker communication between the two parties
+    let (party0_stream, party1_stream) = UnboundedDuplexStream::new_duplex_pair();
+    let party0_fabric = MpcFabric::new(
+        MockNetwork::new(PARTY0, party0_stream),
+        beaver_source(PARTY0),
+    );
+    let party1_fabric = MpcFabric::new(
+        MockNetwork::new(PARTY1, party1_stream),
+        beaver_source(PARTY1),
+    );
+
+    // Spawn two tasks to execute the MPC
+    let party0_task = tokio::spawn(f(party0_fabric.clone()));
+    let party1_task = tokio::spawn(f(party1_fabric.clone()));
+
+    let party0_output = party0_task.await.unwrap();
+    let party1_output = party1_task.await.unwrap();
+
+    // Shutdown the fabrics
+    party0_fabric.shutdown();
+    party1_fabric.shutdown();
+
+    (party0_output, party1_output)
+}
+
+/// Generate a collaborative R1CS proof in process, and open it for each party
+///
+/// Each party builds an `MpcProver` over a transcript labeled with `transcript_label`
+/// and applies `circuit` to it; `circuit` is responsible for committing the party's
+/// witness and specifying the constraints, and returns the commitments the verifier
+/// needs. The proof and the commitments are then generated and opened.
+///
+/// Only the two parties of the `MpcFabric` are simulated, so a circuit cannot be proven
+/// with more than two parties.
+///
+/// Returns the opened proof and commitments of `PARTY0` and `PARTY1`, indexed by
+/// party ID
+#[allow(clippy::type_complexity)]
+pub async fn prove_in_process<F, B, BS>(
+    transcript_label: &'static [u8],
+    pc_gens: PedersenGens,
+    bp_gens: BulletproofGens,
+    beaver_source: B,
+    circuit: F,
+) -> Result<Vec<(R1CSProof, Vec<StarkPoint>)>, MultiproverError>
+where
+    F: 'static
+        + Send
+        + Sync
+        + Fn(&mut MpcProver) -> Result<Vec<AuthenticatedStarkPointOpenResult>, MultiproverError>,
+    B: FnMut(PartyId) -> BS,
+    BS: 'static + SharedValueSource,
+{
+    let circuit = Arc::new(circuit);
+    let bp_gens = Arc::new(bp_gens);
+
+    let (party0_output, party1_output) = execute_mock_mpc_with_beaver_source(
+        move |fabric| {
+            let circuit = circuit.clone();
+            let bp_gens = bp_gens.clone();
+
+            async move {
+                let mut prover =
+                    MpcProver::new_with_fabric(fabric, Transcript::new(transcript_label), pc_gens);
+                let commitments = circuit(&mut prover)?;
+
+                let proof = prover.prove(&bp_gens)?.open().await?;
+                let commitments = join_all(commitments)
+                    .await
+                    .into_iter()
+                    .collect::<Result<Vec<_>, MpcError>>()
+                    .map_err(MultiproverError::Mpc)?;
+
+                Ok((proof, commitments))
+            }
+        },
+        beaver_source,
+    )
+    .await;
+
+    Ok(vec![party0_output?, party1_output?])
+}
@@ -32,6 +32,13 @@ pub trait TranscriptProtocol {
     /// Append a domain separator for a constraint system.
     fn r1cs_domain_sep(&mut self);
 
+    /// Append a domain separator for an aggregated proof of `k` constraint systems.
+    fn r1cs_aggregated_domain_sep(&mut self, k: u64);
+
+    /// Append a domain separator binding a constraint system to the fingerprint of
+    /// its circuit.
+    fn r1cs_circuit_domain_sep(&mut self, fingerprint: &[u8]);
+
     /// Commit a domain separator for a CS without randomized constraints.
     fn r1cs_1phase_domain_sep(&mut self);
 
@@ -76,6 +83,16 @@ impl TranscriptProtocol for HashChainTranscript {
         self.append_message(b"dom-sep", &pad_label(b"r1cs v1"));
     }
 
+    fn r1cs_aggregated_domain_sep(&mut self, k: u64) {
+        self.append_message(b"dom-sep", &pad_label(b"r1cs-aggregated v1"));
+        self.append_u64(b"k", k);
+    }
+
+    fn r1cs_circuit_domain_sep(&mut self, fingerprint: &[u8]) {
+        self.append_message(b"dom-sep", &pad_label(b"r1cs-circuit v1"));
+        self.append_message(b"circuit", fingerprint);
+    }
+
     fn r1cs_1phase_domain_sep(&mut self) {
         self.append_message(b"dom-sep", &pad_label(b"r1cs-1phase"));
     }
@@ -149,6 +166,18 @@ impl MpcTranscript {
         }
     }
 
+    /// Append a domain separator for an `n`-bit, `m`-party range proof.
+    pub fn rangeproof_domain_sep(&mut self, n: u64, m: u64) {
+        let transcript_ref = self.transcript.clone();
+        self.fabric
+            .new_gate_op::<_, Scalar>(vec![self.latest_op_id], move |_args| {
+                let mut locked_transcript = transcript_ref.lock().expect(ERR_LOCK_POISONED);
+                locked_transcript.rangeproof_domain_sep(n, m);
+
+                ResultValue::Scalar(Scalar::zero())
+            });
+    }
+
     /// Append a domain separator for an `n`-bit, `m`-party range proof.
     pub fn innerproduct_domain_sep(&mut self, n: u64) {
         let transcript_ref = self.transcript.clone();
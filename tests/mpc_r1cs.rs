@@ -1,212 +1,31 @@
 #![allow(non_snake_case)]
 
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use futures::future::join_all;
 use itertools::Itertools;
 use merlin::HashChainTranscript as Transcript;
+use mpc_bulletproof::r1cs::{ConstraintSystem, Prover, R1CSError, R1CSProof, Verifier};
 use mpc_bulletproof::{
     r1cs_mpc::{
-        MpcConstraintSystem, MpcLinearCombination, MpcProver, MpcRandomizableConstraintSystem,
-        MpcRandomizedConstraintSystem, MpcVariable, MultiproverError, PartiallySharedR1CSProof,
+        CircomCircuit, CircomWitness, GenericRandomizableConstraintSystem, MpcConstraintSystem,
+        MpcLinearCombination, MpcProver, MultiproverError, PartiallySharedR1CSProof,
+        PreprocessingCost,
+    },
+    testing::{
+        execute_mock_mpc, execute_mock_mpc_with_beaver_source, prove_in_process, MockNetwork,
+        PartyIDBeaverSource, UnboundedDuplexStream,
     },
     BulletproofGens, PedersenGens,
 };
 use mpc_stark::algebra::authenticated_stark_point::AuthenticatedStarkPointOpenResult;
-use mpc_stark::error::MpcNetworkError;
-use mpc_stark::{
-    algebra::scalar::Scalar, beaver::SharedValueSource, network::MpcNetwork, MpcFabric, PARTY0,
-    PARTY1,
-};
+use mpc_stark::{algebra::scalar::Scalar, beaver::SharedValueSource, MpcFabric, PARTY0, PARTY1};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use rand::Rng;
-use std::{
-    pin::Pin,
-    task::{Context, Poll},
-};
-
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
-
-use mpc_stark::network::NetworkOutbound;
-
-use async_trait::async_trait;
-use futures::future::join_all;
-use futures::{Future, Sink, Stream};
-use mpc_bulletproof::r1cs::{
-    ConstraintSystem, R1CSError, RandomizableConstraintSystem, RandomizedConstraintSystem,
-    Variable, Verifier,
-};
-use mpc_stark::network::PartyId;
-
-/// An implementation of a beaver value source that returns
-/// beaver triples (0, 0, 0) for party 0 and (1, 1, 1) for party 1
-#[derive(Clone, Debug, Default)]
-pub struct PartyIDBeaverSource {
-    /// The ID of the local party
-    party_id: u64,
-}
-
-impl PartyIDBeaverSource {
-    /// Create a new beaver source given the local party_id
-    pub fn new(party_id: u64) -> Self {
-        Self { party_id }
-    }
-}
-
-/// The PartyIDBeaverSource returns beaver triplets split statically between the
-/// parties. We assume a = 2, b = 3 ==> c = 6. [a] = (1, 1); [b] = (3, 0) [c] = (2, 4)
-impl SharedValueSource for PartyIDBeaverSource {
-    fn next_shared_bit(&mut self) -> Scalar {
-        // Simply output partyID, assume partyID \in {0, 1}
-        assert!(self.party_id == 0 || self.party_id == 1);
-        Scalar::from(self.party_id)
-    }
-
-    fn next_triplet(&mut self) -> (Scalar, Scalar, Scalar) {
-        if self.party_id == 0 {
-            (Scalar::from(1u64), Scalar::from(3u64), Scalar::from(2u64))
-        } else {
-            (Scalar::from(1u64), Scalar::from(0u64), Scalar::from(4u64))
-        }
-    }
-
-    fn next_shared_inverse_pair(&mut self) -> (Scalar, Scalar) {
-        (Scalar::from(self.party_id), Scalar::from(self.party_id))
-    }
-
-    fn next_shared_value(&mut self) -> Scalar {
-        Scalar::from(self.party_id)
-    }
-}
-
-/// A dummy MPC network that operates over a duplex channel instead of a network connection/// An unbounded duplex channel used to mock a network connection
-pub struct UnboundedDuplexStream {
-    /// The send side of the stream
-    send: UnboundedSender<NetworkOutbound>,
-    /// The receive side of the stream
-    recv: UnboundedReceiver<NetworkOutbound>,
-}
-
-impl UnboundedDuplexStream {
-    /// Create a new pair of duplex streams
-    pub fn new_duplex_pair() -> (Self, Self) {
-        let (send1, recv1) = unbounded_channel();
-        let (send2, recv2) = unbounded_channel();
-
-        (
-            Self {
-                send: send1,
-                recv: recv2,
-            },
-            Self {
-                send: send2,
-                recv: recv1,
-            },
-        )
-    }
-
-    /// Send a message on the stream
-    pub fn send(&mut self, msg: NetworkOutbound) {
-        self.send.send(msg).unwrap();
-    }
-
-    /// Recv a message from the stream
-    pub async fn recv(&mut self) -> NetworkOutbound {
-        self.recv.recv().await.unwrap()
-    }
-}
-
-/// A dummy network implementation used for unit testing
-pub struct MockNetwork {
-    /// The ID of the local party
-    party_id: PartyId,
-    /// The underlying mock network connection
-    mock_conn: UnboundedDuplexStream,
-}
-
-impl MockNetwork {
-    /// Create a new mock network from one half of a duplex stream
-    pub fn new(party_id: PartyId, stream: UnboundedDuplexStream) -> Self {
-        Self {
-            party_id,
-            mock_conn: stream,
-        }
-    }
-}
-
-#[async_trait]
-impl MpcNetwork for MockNetwork {
-    fn party_id(&self) -> PartyId {
-        self.party_id
-    }
-
-    async fn close(&mut self) -> Result<(), MpcNetworkError> {
-        Ok(())
-    }
-}
-
-impl Stream for MockNetwork {
-    type Item = Result<NetworkOutbound, MpcNetworkError>;
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Box::pin(self.mock_conn.recv())
-            .as_mut()
-            .poll(cx)
-            .map(|value| Some(Ok(value)))
-    }
-}
-
-impl Sink<NetworkOutbound> for MockNetwork {
-    type Error = MpcNetworkError;
-
-    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
-    }
-
-    fn start_send(mut self: Pin<&mut Self>, item: NetworkOutbound) -> Result<(), Self::Error> {
-        self.mock_conn.send(item);
-        Ok(())
-    }
-
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
-    }
-
-    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
-    }
-}
-
-pub async fn execute_mock_mpc<T, S, F>(mut f: F) -> (T, T)
-where
-    T: Send + 'static,
-    S: Future<Output = T> + Send + 'static,
-    F: FnMut(MpcFabric) -> S,
-{
-    // Build a duplex stream to broker communication between the two parties
-    let (party0_stream, party1_stream) = UnboundedDuplexStream::new_duplex_pair();
-    let party0_fabric = MpcFabric::new(
-        MockNetwork::new(PARTY0, party0_stream),
-        PartyIDBeaverSource::new(PARTY0),
-    );
-    let party1_fabric = MpcFabric::new(
-        MockNetwork::new(PARTY1, party1_stream),
-        PartyIDBeaverSource::new(PARTY1),
-    );
-
-    // Spawn two tasks to execute the MPC
-    let fabric0 = party0_fabric.clone();
-    let fabric1 = party1_fabric.clone();
-    let party0_task = tokio::spawn(f(fabric0));
-    let party1_task = tokio::spawn(f(fabric1));
-
-    let party0_output = party0_task.await.unwrap();
-    let party1_output = party1_task.await.unwrap();
-
-    // Shutdown the fabrics
-    party0_fabric.shutdown();
-    party1_fabric.shutdown();
-
-    (party0_output, party1_output)
-}
 
 // Shuffle gadget (documented in markdown file)
 
@@ -214,16 +33,17 @@ where
 struct MpcShuffleProof(PartiallySharedR1CSProof);
 
 impl MpcShuffleProof {
-    fn gadget<CS: MpcRandomizableConstraintSystem>(
+    /// The shuffle gadget, applied by both the collaborative prover and the verifier
+    fn gadget<CS: GenericRandomizableConstraintSystem>(
         cs: &mut CS,
-        x: Vec<MpcVariable>,
-        y: Vec<MpcVariable>,
+        x: Vec<CS::Variable>,
+        y: Vec<CS::Variable>,
     ) -> Result<(), R1CSError> {
         assert_eq!(x.len(), y.len());
         let k = x.len();
 
         if k == 1 {
-            cs.constrain(&y[0] - &x[0]);
+            cs.constrain(y[0].clone() - x[0].clone());
             return Ok(());
         }
 
@@ -231,22 +51,24 @@ impl MpcShuffleProof {
             let z = cs.challenge_scalar(b"shuffle challenge");
 
             // Make last x multiplier for i = k-1 and k-2
-            let Ok((_, _, last_mulx_out)) = cs.multiply(&(&x[k - 1] - &z), &(&x[k - 2] - &z)) else { todo!() };
+            let (_, _, last_mulx_out) =
+                cs.multiply(x[k - 1].clone() - z.clone(), x[k - 2].clone() - z.clone())?;
 
             // Make multipliers for x from i == [0, k-3]
-            let first_mulx_out = (0..k - 2).rev().fold(last_mulx_out, |prev_out, i| {
-                let Ok((_, _, o)) = cs.multiply(&MpcLinearCombination::from(prev_out), &(&x[i] - &z)) else { todo!() };
-                o
-            });
+            let first_mulx_out = (0..k - 2).rev().try_fold(last_mulx_out, |prev_out, i| {
+                let (_, _, o) = cs.multiply(prev_out.into(), x[i].clone() - z.clone())?;
+                Ok::<_, R1CSError>(o)
+            })?;
 
             // Make last y multiplier for i = k-1 and k-2
-            let Ok((_, _, last_muly_out)) = cs.multiply(&(&y[k - 1] - &z), &(&y[k - 2] - &z)) else { todo!() };
+            let (_, _, last_muly_out) =
+                cs.multiply(y[k - 1].clone() - z.clone(), y[k - 2].clone() - z.clone())?;
 
             // Make multipliers for y from i == [0, k-3]
-            let first_muly_out = (0..k - 2).rev().fold(last_muly_out, |prev_out, i| {
-                let Ok((_, _, o)) = cs.multiply(&MpcLinearCombination::from(prev_out), &(&y[i] - &z)) else { todo!() };
-                o
-            });
+            let first_muly_out = (0..k - 2).rev().try_fold(last_muly_out, |prev_out, i| {
+                let (_, _, o) = cs.multiply(prev_out.into(), y[i].clone() - z.clone())?;
+                Ok::<_, R1CSError>(o)
+            })?;
 
             // Constrain last x mul output and last y mul output to be equal
             cs.constrain(first_mulx_out - first_muly_out);
@@ -316,41 +138,6 @@ impl MpcShuffleProof {
 }
 
 impl MpcShuffleProof {
-    fn single_prover_gadget<CS: RandomizableConstraintSystem>(
-        cs: &mut CS,
-        x: Vec<Variable>,
-        y: Vec<Variable>,
-    ) -> Result<(), R1CSError> {
-        assert_eq!(x.len(), y.len());
-        let k = x.len();
-
-        if k == 1 {
-            cs.constrain(y[0] - x[0]);
-            return Ok(());
-        }
-
-        cs.specify_randomized_constraints(move |cs| {
-            let z = cs.challenge_scalar(b"shuffle challenge");
-            let (_, _, last_mulx_out) = cs.multiply(x[k - 1] - z, x[k - 2] - z);
-            let first_mulx_out = (0..k - 2).rev().fold(last_mulx_out, |acc, i| {
-                let (_, _, o) = cs.multiply(acc.into(), x[i] - z);
-                o
-            });
-
-            let (_, _, last_muly_out) = cs.multiply(y[k - 1] - z, y[k - 2] - z);
-            let first_muly_out = (0..k - 2).rev().fold(last_muly_out, |acc, i| {
-                let (_, _, o) = cs.multiply(acc.into(), y[i] - z);
-                o
-            });
-
-            cs.constrain(first_mulx_out - first_muly_out);
-
-            Ok(())
-        })?;
-
-        Ok(())
-    }
-
     /// Attempt to verify a `ShuffleProof`.
     pub async fn verify(
         &self,
@@ -387,7 +174,7 @@ impl MpcShuffleProof {
             .map(|V| verifier.commit(*V))
             .collect_vec();
 
-        Self::single_prover_gadget(&mut verifier, input_vars, output_vars)
+        Self::gadget(&mut verifier, input_vars, output_vars)
             .map_err(MultiproverError::ProverError)?;
         verifier
             .verify(&opened_proof, bp_gens)
@@ -446,3 +233,645 @@ async fn mpc_shuffle_proof_test() {
     })
     .await;
 }
+
+/// A randomized gadget that checks that `x` and `y` are equal by comparing their
+/// combinations weighted by challenges, applied by both the collaborative prover and
+/// the verifier
+fn weighted_equality_gadget<CS: GenericRandomizableConstraintSystem>(
+    cs: &mut CS,
+    x: Vec<CS::Variable>,
+    y: Vec<CS::Variable>,
+) -> Result<(), R1CSError> {
+    cs.specify_randomized_constraints(move |cs| {
+        let mut difference = cs.constant(Scalar::zero());
+        for (x_i, y_i) in x.iter().zip(y.iter()) {
+            // Weight variables and linear combinations alike by the challenge
+            let r = cs.challenge_scalar(b"equality challenge");
+            difference =
+                difference + x_i.clone() * r.clone() - CS::LinearCombination::from(y_i.clone()) * r;
+        }
+
+        let (_, _, out) = cs.multiply(difference, cs.constant(Scalar::one()))?;
+        cs.constrain(out.into());
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn mpc_generic_randomized_gadget_test() {
+    // The collaborative prover multiplies by challenges sampled in the MPC, the single
+    // verifier by public challenges
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(1, 1);
+    let x = [1u64, 2, 3].map(Scalar::from);
+
+    let (res0, res1) = execute_mock_mpc(|fabric| {
+        let bp_gens = bp_gens.clone();
+
+        async move {
+            let mut results = Vec::new();
+            for y in [[1u64, 2, 3], [1, 2, 4]].map(|y| y.map(Scalar::from)) {
+                let blindings = (0..3)
+                    .map(|_| Scalar::random(&mut thread_rng()))
+                    .collect_vec();
+                let mut prover = MpcProver::new_with_fabric(
+                    fabric.clone(),
+                    Transcript::new(b"WeightedEqualityTest"),
+                    pc_gens,
+                );
+                let (x_commitments, x_vars) = prover.batch_commit(PARTY0, x, &blindings).unwrap();
+                let (y_commitments, y_vars) = prover.batch_commit(PARTY1, y, &blindings).unwrap();
+                weighted_equality_gadget(&mut prover, x_vars, y_vars).unwrap();
+                let proof = prover.prove(&bp_gens).unwrap().open().await.unwrap();
+
+                let x_commitments = join_all(x_commitments).await;
+                let y_commitments = join_all(y_commitments).await;
+
+                let mut transcript = Transcript::new(b"WeightedEqualityTest");
+                let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+                let x_vars = x_commitments
+                    .into_iter()
+                    .map(|commitment| verifier.commit(commitment.unwrap()))
+                    .collect_vec();
+                let y_vars = y_commitments
+                    .into_iter()
+                    .map(|commitment| verifier.commit(commitment.unwrap()))
+                    .collect_vec();
+                weighted_equality_gadget(&mut verifier, x_vars, y_vars).unwrap();
+                results.push(verifier.verify(&proof, &bp_gens).is_ok());
+            }
+
+            results
+        }
+    })
+    .await;
+
+    assert_eq!(res0, vec![true, false]);
+    assert_eq!(res1, vec![true, false]);
+}
+
+#[tokio::test]
+async fn mpc_prover_custom_network_test() {
+    let pc_gens = PedersenGens::default();
+    let value = Scalar::from(42u64);
+
+    // Build each party's prover directly over its end of the mock network, with
+    // blinding shares 2 and 3
+    let (party0_stream, party1_stream) = UnboundedDuplexStream::new_duplex_pair();
+    let tasks = [(PARTY0, party0_stream, 2u64), (PARTY1, party1_stream, 3u64)].map(
+        |(party_id, stream, blinding_share)| {
+            tokio::spawn(async move {
+                let mut prover = MpcProver::new_with_network(
+                    MockNetwork::new(party_id, stream),
+                    PartyIDBeaverSource::new(party_id),
+                    Transcript::new(b"CustomNetworkTest"),
+                    pc_gens,
+                );
+
+                let (commitment, _) = prover
+                    .commit(PARTY0, value, Scalar::from(blinding_share))
+                    .unwrap();
+                commitment.await.unwrap()
+            })
+        },
+    );
+
+    let expected = pc_gens.commit(value, Scalar::from(5u64));
+    for task in tasks {
+        assert_eq!(task.await.unwrap(), expected);
+    }
+}
+
+#[tokio::test]
+async fn mpc_public_input_test() {
+    // Prove that a privately committed value equals a public input, and that a
+    // proof against a different public input does not verify
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(1, 1);
+    let (value, public_input) = (Scalar::from(17u64), Scalar::from(17u64));
+
+    let (res0, res1) = execute_mock_mpc(|fabric| async move {
+        let mut prover =
+            MpcProver::new_with_fabric(fabric, Transcript::new(b"PublicInputTest"), pc_gens);
+        let (commitment, x) = prover
+            .commit(PARTY0, value, Scalar::random(&mut thread_rng()))
+            .unwrap();
+        let y = prover.commit_public(public_input);
+
+        prover.constrain(&x - &y);
+        prover.multiply(&x.into(), &y.into()).unwrap();
+
+        let proof = prover.prove(&bp_gens).unwrap().open().await.unwrap();
+        let commitment = commitment.await.unwrap();
+
+        [public_input, public_input + Scalar::one()].map(|verifier_input| {
+            let mut transcript = Transcript::new(b"PublicInputTest");
+            let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+            let x = verifier.commit(commitment);
+            let y = verifier.commit_public(verifier_input);
+
+            verifier.constrain(x - y);
+            verifier.multiply(x.into(), y.into());
+            verifier.verify(&proof, &bp_gens).is_ok()
+        })
+    })
+    .await;
+
+    assert_eq!(res0, [true, false]);
+    assert_eq!(res1, [true, false]);
+}
+
+#[tokio::test]
+async fn mpc_circuit_weights_test() {
+    // The collaborative prover and the single-prover verifier should build
+    // structurally identical circuits from the same gadget
+    let pc_gens = PedersenGens::default();
+
+    let (res0, res1) = execute_mock_mpc(|fabric| async move {
+        let mut prover =
+            MpcProver::new_with_fabric(fabric.clone(), Transcript::new(b"WeightsTest"), pc_gens);
+        let (_, vars) = prover
+            .batch_commit(
+                PARTY0,
+                [Scalar::from(7u64), Scalar::from(2u64)],
+                &[
+                    Scalar::random(&mut thread_rng()),
+                    Scalar::random(&mut thread_rng()),
+                ],
+            )
+            .unwrap();
+        let z = prover.commit_public(Scalar::from(14u64));
+
+        let (_, _, o) = prover
+            .multiply(&vars[0].clone().into(), &vars[1].clone().into())
+            .unwrap();
+        prover.constrain(o - z);
+        prover.constrain(
+            &vars[0] - &vars[1] - MpcLinearCombination::from_scalar(Scalar::from(5u64), fabric),
+        );
+
+        let (num_constraints, num_multipliers, weights) = {
+            let mut transcript = Transcript::new(b"WeightsTest");
+            let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+            let x = verifier.commit(pc_gens.commit(Scalar::zero(), Scalar::zero()));
+            let y = verifier.commit(pc_gens.commit(Scalar::zero(), Scalar::zero()));
+            let z = verifier.commit_public(Scalar::from(14u64));
+
+            let (_, _, o) = verifier.multiply(x.into(), y.into());
+            verifier.constrain(o - z);
+            verifier.constrain(x - y - Scalar::from(5u64));
+
+            (
+                verifier.num_constraints(),
+                verifier.num_multipliers(),
+                verifier.get_weights(),
+            )
+        };
+
+        prover.num_constraints() == num_constraints
+            && prover.num_multipliers() == num_multipliers
+            && prover.get_weights().await == weights
+    })
+    .await;
+
+    assert!(res0 && res1);
+}
+
+#[tokio::test]
+async fn prove_in_process_test() {
+    // Both parties should open the same proof, which verifies against the
+    // commitment to the shared witness
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(1, 1);
+    let blinding = Scalar::from(3u64);
+
+    let proofs = prove_in_process(
+        b"InProcessTest",
+        pc_gens,
+        bp_gens.clone(),
+        PartyIDBeaverSource::new,
+        move |prover| {
+            // Party 0 owns the witness, the blinding factor is shared as (3, 0)
+            let blinding_share = if prover.party_id() == PARTY0 {
+                blinding
+            } else {
+                Scalar::zero()
+            };
+            let (commitment, x) = prover
+                .commit(PARTY0, Scalar::from(6u64), blinding_share)
+                .map_err(MultiproverError::Mpc)?;
+            let (_, _, o) = prover.multiply(&x.clone().into(), &x.into())?;
+            let square = prover.commit_public(Scalar::from(36u64));
+            prover.constrain(o - square);
+
+            Ok(vec![commitment])
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(proofs.len(), 2);
+    for (proof, commitments) in proofs.iter() {
+        assert_eq!(
+            commitments,
+            &vec![pc_gens.commit(Scalar::from(6u64), blinding)]
+        );
+
+        let mut transcript = Transcript::new(b"InProcessTest");
+        let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+        let x = verifier.commit(commitments[0]);
+        let square = verifier.commit_public(Scalar::from(36u64));
+        let (_, _, o) = verifier.multiply(x.into(), x.into());
+        verifier.constrain(o - square);
+
+        assert!(verifier.verify(proof, &bp_gens).is_ok());
+    }
+}
+
+#[tokio::test]
+async fn open_to_party_test() {
+    // Party 0 owns the witness, the proof is opened only to party 1
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(1, 1);
+    let blinding = Scalar::from(3u64);
+
+    let (proof0, proof1) = execute_mock_mpc(|fabric| {
+        let bp_gens = bp_gens.clone();
+
+        async move {
+            let blinding_share = if fabric.party_id() == PARTY0 {
+                blinding
+            } else {
+                Scalar::zero()
+            };
+
+            let mut prover =
+                MpcProver::new_with_fabric(fabric, Transcript::new(b"OpenToTest"), pc_gens);
+            let (_, x) = prover
+                .commit(PARTY0, Scalar::from(6u64), blinding_share)
+                .unwrap();
+            let (_, _, o) = prover.multiply(&x.clone().into(), &x.into()).unwrap();
+            let square = prover.commit_public(Scalar::from(36u64));
+            prover.constrain(o - square);
+
+            let proof = prover.prove(&bp_gens).unwrap();
+            proof.open_to(PARTY1).await.unwrap()
+        }
+    })
+    .await;
+
+    assert!(proof0.is_none());
+    let proof = proof1.unwrap();
+
+    let mut transcript = Transcript::new(b"OpenToTest");
+    let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+    let x = verifier.commit(pc_gens.commit(Scalar::from(6u64), blinding));
+    let square = verifier.commit_public(Scalar::from(36u64));
+    let (_, _, o) = verifier.multiply(x.into(), x.into());
+    verifier.constrain(o - square);
+
+    assert!(verifier.verify(&proof, &bp_gens).is_ok());
+}
+
+/// A beaver source that counts the preprocessing drawn from it
+struct CountingBeaverSource {
+    /// The underlying beaver source
+    inner: PartyIDBeaverSource,
+    /// The number of triples drawn, shared with the test
+    triples: Arc<AtomicUsize>,
+    /// The number of shared values drawn, shared with the test
+    shared_values: Arc<AtomicUsize>,
+}
+
+impl SharedValueSource for CountingBeaverSource {
+    fn next_shared_bit(&mut self) -> Scalar {
+        self.inner.next_shared_bit()
+    }
+
+    fn next_triplet(&mut self) -> (Scalar, Scalar, Scalar) {
+        self.triples.fetch_add(1, Ordering::SeqCst);
+        self.inner.next_triplet()
+    }
+
+    fn next_shared_inverse_pair(&mut self) -> (Scalar, Scalar) {
+        self.inner.next_shared_inverse_pair()
+    }
+
+    fn next_shared_value(&mut self) -> Scalar {
+        self.shared_values.fetch_add(1, Ordering::SeqCst);
+        self.inner.next_shared_value()
+    }
+}
+
+/// Prove `circuit` in process, counting the preprocessing drawn by both parties for
+/// the proof beyond the baseline drawn by their fabrics
+///
+/// Returns the number of triples and shared values drawn, and the opened proof of
+/// party 0
+async fn count_preprocessing<F>(
+    label: &'static [u8],
+    bp_gens: BulletproofGens,
+    circuit: F,
+) -> (usize, usize, R1CSProof)
+where
+    F: 'static
+        + Send
+        + Sync
+        + Fn(&mut MpcProver) -> Result<Vec<AuthenticatedStarkPointOpenResult>, MultiproverError>,
+{
+    let triples = Arc::new(AtomicUsize::new(0));
+    let shared_values = Arc::new(AtomicUsize::new(0));
+    let beaver_source = {
+        let triples = triples.clone();
+        let shared_values = shared_values.clone();
+        move |party_id| CountingBeaverSource {
+            inner: PartyIDBeaverSource::new(party_id),
+            triples: triples.clone(),
+            shared_values: shared_values.clone(),
+        }
+    };
+
+    execute_mock_mpc_with_beaver_source(|_| async {}, beaver_source.clone()).await;
+    let base_triples = triples.load(Ordering::SeqCst);
+    let base_shared_values = shared_values.load(Ordering::SeqCst);
+
+    let mut proofs = prove_in_process(
+        label,
+        PedersenGens::default(),
+        bp_gens,
+        beaver_source,
+        circuit,
+    )
+    .await
+    .unwrap();
+
+    // The baseline is drawn again by the fabrics of the proof
+    (
+        triples.load(Ordering::SeqCst) - 2 * base_triples,
+        shared_values.load(Ordering::SeqCst) - 2 * base_shared_values,
+        proofs.remove(0).0,
+    )
+}
+
+#[tokio::test]
+async fn preprocessing_cost_test() {
+    let (triples, shared_values, _) = count_preprocessing(
+        b"PreprocessingCostTest",
+        BulletproofGens::new(1, 1),
+        |prover| {
+            let (commitment, x) = prover
+                .commit(PARTY0, Scalar::from(6u64), Scalar::one())
+                .map_err(MultiproverError::Mpc)?;
+            let (_, _, o) = prover.multiply(&x.clone().into(), &x.into())?;
+            let square = prover.commit_public(Scalar::from(36u64));
+            prover.constrain(o - square);
+
+            // One multiplier and two commitments
+            assert_eq!(
+                prover.preprocessing_cost(),
+                Some(PreprocessingCost::new(1, 0, 2))
+            );
+            Ok(vec![commitment])
+        },
+    )
+    .await;
+
+    // Building the circuit draws one triple per party for the multiplication gate
+    let cost = PreprocessingCost::new(1, 0, 2);
+    assert_eq!(triples, 2 * (cost.triples + 1));
+    assert_eq!(shared_values, 2 * cost.shared_randoms);
+}
+
+#[tokio::test]
+async fn preprocessing_cost_randomized_test() {
+    // The shuffle gadget allocates all of its `2 * (k - 1)` multipliers in the
+    // randomized phase, which pads to 8 multipliers for `k = 4`
+    let k = 4;
+    let (triples, shared_values, proof) = count_preprocessing(
+        b"PreprocessingCostRandomizedTest",
+        BulletproofGens::new(8, 1),
+        move |prover| {
+            let input = (0..k as u64).map(Scalar::from).collect_vec();
+            let blinders = (0..k)
+                .map(|_| Scalar::random(&mut thread_rng()))
+                .collect_vec();
+            let (mut commitments, x) = prover
+                .batch_commit(PARTY0, input.iter().copied(), &blinders)
+                .map_err(MultiproverError::Mpc)?;
+            let (output_commitments, y) = prover
+                .batch_commit(PARTY1, input.into_iter().rev(), &blinders)
+                .map_err(MultiproverError::Mpc)?;
+            commitments.extend(output_commitments);
+
+            MpcShuffleProof::gadget(prover, x, y).map_err(MultiproverError::ProverError)?;
+
+            // The randomized constraints have not allocated their multipliers yet
+            assert_eq!(prover.preprocessing_cost(), None);
+            Ok(commitments)
+        },
+    )
+    .await;
+
+    let cost = PreprocessingCost::new(0, 2 * (k - 1), 2 * k);
+    assert_eq!(triples, 2 * cost.triples);
+    assert_eq!(shared_values, 2 * cost.shared_randoms);
+
+    // One round for each of the phase one commitments, the phase two commitments, the
+    // polynomial commitments, the evaluation of t(x), each IPP round and the IPP scalars
+    assert!(!proof.A_I2.is_identity());
+    assert_eq!(proof.ipp_proof.L_vec.len(), 3);
+    assert_eq!(cost.opening_rounds, 5 + proof.ipp_proof.L_vec.len());
+}
+
+// Circom import
+
+/// Encode a scalar as a little-endian circom field element
+fn circom_scalar(value: Scalar) -> Vec<u8> {
+    let mut bytes = value.to_bytes_be();
+    bytes.reverse();
+    bytes
+}
+
+/// Encode a circom binary file from its sections
+fn circom_file(magic: &[u8; 4], version: u32, sections: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut buf = magic.to_vec();
+    buf.extend_from_slice(&version.to_le_bytes());
+    buf.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+    for (section_type, contents) in sections {
+        buf.extend_from_slice(&section_type.to_le_bytes());
+        buf.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        buf.extend_from_slice(contents);
+    }
+
+    buf
+}
+
+/// Encode the field of a circom file, the Stark scalar field
+fn circom_field() -> Vec<u8> {
+    // The modulus is odd, so adding one to `-1` does not carry
+    let mut prime = circom_scalar(-Scalar::one());
+    prime[0] += 1;
+
+    let mut buf = 32u32.to_le_bytes().to_vec();
+    buf.extend_from_slice(&prime);
+    buf
+}
+
+/// Encode the `.r1cs` file of a circuit over the wires `[1, out, a, b]`, where `out`
+/// is a public output, enforcing `a * b = out - 1` and `0 = a - 3`
+fn circom_r1cs_bytes() -> Vec<u8> {
+    let mut header = circom_field();
+    for count in [4u32, 1, 0, 2] {
+        header.extend_from_slice(&count.to_le_bytes());
+    }
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&2u32.to_le_bytes());
+
+    let constraints: [[Vec<(u32, Scalar)>; 3]; 2] = [
+        [
+            vec![(2, Scalar::one())],
+            vec![(3, Scalar::one())],
+            vec![(0, -Scalar::one()), (1, Scalar::one())],
+        ],
+        [
+            vec![],
+            vec![],
+            vec![(0, -Scalar::from(3u64)), (2, Scalar::one())],
+        ],
+    ];
+    let mut body = Vec::new();
+    for combination in constraints.iter().flatten() {
+        body.extend_from_slice(&(combination.len() as u32).to_le_bytes());
+        for (wire, coeff) in combination {
+            body.extend_from_slice(&wire.to_le_bytes());
+            body.extend_from_slice(&circom_scalar(*coeff));
+        }
+    }
+
+    circom_file(b"r1cs", 1, &[(1, header), (2, body)])
+}
+
+/// Encode the `.wtns` file of a witness
+fn circom_wtns_bytes(values: &[u64]) -> Vec<u8> {
+    let mut header = circom_field();
+    header.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    let body = values
+        .iter()
+        .flat_map(|value| circom_scalar(Scalar::from(*value)))
+        .collect_vec();
+
+    circom_file(b"wtns", 2, &[(1, header), (2, body)])
+}
+
+/// Prove a circom witness with the single prover, and verify it against the given
+/// public signals
+fn circom_helper(witness: &[u64], verifier_public: &[Scalar]) -> Result<(), R1CSError> {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(8, 1);
+    let circuit = CircomCircuit::from_r1cs_bytes(&circom_r1cs_bytes())?;
+    let witness = CircomWitness::from_wtns_bytes(&circom_wtns_bytes(witness))?;
+
+    let proof = {
+        let mut transcript = Transcript::new(b"CircomTest");
+        let mut prover = Prover::new(&pc_gens, &mut transcript);
+        let private_signals = circuit.private_signals(&witness)?.to_vec();
+        circuit.synthesize(
+            &mut prover,
+            circuit.public_signals(&witness)?,
+            Some(private_signals),
+        )?;
+        prover.prove(&bp_gens)?
+    };
+
+    let mut transcript = Transcript::new(b"CircomTest");
+    let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+    circuit.synthesize(&mut verifier, verifier_public, None)?;
+    verifier.verify(&proof, &bp_gens)
+}
+
+#[test]
+fn circom_import_test() {
+    let circuit = CircomCircuit::from_r1cs_bytes(&circom_r1cs_bytes()).unwrap();
+    assert_eq!(circuit.num_wires(), 4);
+    assert_eq!(circuit.num_public(), 1);
+    assert_eq!(circuit.num_constraints(), 2);
+
+    assert!(circom_helper(&[1, 7, 3, 2], &[Scalar::from(7u64)]).is_ok());
+    assert_eq!(
+        circom_helper(&[1, 7, 3, 2], &[Scalar::from(8u64)]),
+        Err(R1CSError::VerificationError)
+    );
+    assert_eq!(
+        circom_helper(&[1, 9, 4, 2], &[Scalar::from(9u64)]),
+        Err(R1CSError::VerificationError)
+    );
+
+    // A witness for a different circuit is rejected
+    let witness = CircomWitness::from_wtns_bytes(&circom_wtns_bytes(&[1, 7, 3])).unwrap();
+    assert!(matches!(
+        circuit.public_signals(&witness),
+        Err(R1CSError::GadgetError { .. })
+    ));
+
+    // Files over another field are rejected
+    let mut r1cs = circom_r1cs_bytes();
+    r1cs[28] ^= 1;
+    assert!(matches!(
+        CircomCircuit::from_r1cs_bytes(&r1cs),
+        Err(R1CSError::GadgetError { .. })
+    ));
+
+    let r1cs = circom_r1cs_bytes();
+    assert_eq!(
+        CircomCircuit::from_r1cs_bytes(&r1cs[..r1cs.len() - 1]),
+        Err(R1CSError::FormatError)
+    );
+    assert_eq!(
+        CircomWitness::from_wtns_bytes(b"wtns"),
+        Err(R1CSError::FormatError)
+    );
+}
+
+#[tokio::test]
+async fn mpc_circom_import_test() {
+    // Party 0 holds the witness, the parties prove it collaboratively and the proof
+    // verifies against a verifier rebuilt from the same circuit. Only the public
+    // signals are known to both parties
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(8, 1);
+    let circuit = CircomCircuit::from_r1cs_bytes(&circom_r1cs_bytes()).unwrap();
+    let witness = CircomWitness::from_wtns_bytes(&circom_wtns_bytes(&[1, 7, 3, 2])).unwrap();
+    let public_signals = circuit.public_signals(&witness).unwrap().to_vec();
+
+    let (res0, res1) = execute_mock_mpc(|fabric| {
+        // Party 1 does not receive the witness
+        let witness = (fabric.party_id() == PARTY0).then(|| witness.clone());
+        let (circuit, public_signals) = (circuit.clone(), public_signals.clone());
+        let bp_gens = bp_gens.clone();
+
+        async move {
+            let mut prover =
+                MpcProver::new_with_fabric(fabric.clone(), Transcript::new(b"CircomTest"), pc_gens);
+            // Party 1 inputs placeholders for the values shared by party 0
+            let private_values = match &witness {
+                Some(witness) => circuit.private_signals(witness).unwrap().to_vec(),
+                None => vec![Scalar::zero(); circuit.num_wires() - 1 - circuit.num_public()],
+            };
+            let private_signals = fabric.batch_share_scalar(private_values, PARTY0);
+            circuit
+                .synthesize(&mut prover, &public_signals, Some(private_signals))
+                .unwrap();
+            let proof = prover.prove(&bp_gens).unwrap().open().await.unwrap();
+
+            let mut transcript = Transcript::new(b"CircomTest");
+            let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+            circuit
+                .synthesize(&mut verifier, &public_signals, None)
+                .unwrap();
+            verifier.verify(&proof, &bp_gens).is_ok()
+        }
+    })
+    .await;
+
+    assert!(res0 && res1);
+}
@@ -0,0 +1,144 @@
+use futures::future::join_all;
+use merlin::HashChainTranscript as Transcript;
+use mpc_bulletproof::{
+    r1cs_mpc::{MultiproverError, ProofStep, SharedRangeProof},
+    testing::execute_mock_mpc,
+    BulletproofGens, MpcTranscript, PedersenGens, ProofError,
+};
+use mpc_stark::{
+    algebra::{scalar::Scalar, stark_curve::StarkPoint},
+    error::MpcError,
+    MpcFabric, PARTY0, PARTY1,
+};
+use rand::thread_rng;
+
+/// The shares party 0 alters before proving, to check that the alteration is caught
+#[derive(Clone, Copy, Default)]
+struct Tamper {
+    /// Added to party 0's share of the value
+    value: u64,
+    /// Added to party 0's share of the blinding factor
+    blinding: u64,
+}
+
+/// Prove that `value`, shared by party 0, lies in `[0, 2^n)`, then open and verify the
+/// proof; party 1 contributes its share of `value` without knowing it
+async fn prove_and_verify(
+    value: u64,
+    n: usize,
+    tamper: Tamper,
+    fabric: MpcFabric,
+) -> Result<(), MultiproverError> {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(n, 1);
+
+    let value = fabric.share_scalar(value, PARTY0);
+    let blinding = fabric.share_scalar(Scalar::random(&mut thread_rng()), PARTY0);
+
+    // Both parties add to their shares so that their operations stay in sync, only
+    // party 0 adds a non-zero offset
+    let (value_offset, blinding_offset) = if fabric.party_id() == PARTY0 {
+        (tamper.value, tamper.blinding)
+    } else {
+        (0, 0)
+    };
+    let value = value + Scalar::from(value_offset);
+    let blinding = blinding + Scalar::from(blinding_offset);
+
+    let mut transcript = MpcTranscript::new(Transcript::new(b"RangeProofTest"), fabric.clone());
+    let (proof, commitment) = SharedRangeProof::prove_single(
+        &bp_gens,
+        &pc_gens,
+        &mut transcript,
+        &value,
+        &blinding,
+        n,
+        &fabric,
+    )
+    .await?;
+
+    let proof = proof.open().await?;
+    let commitments: Vec<StarkPoint> = join_all([commitment])
+        .await
+        .into_iter()
+        .collect::<Result<_, MpcError>>()
+        .map_err(MultiproverError::Mpc)?;
+
+    let mut transcript = Transcript::new(b"RangeProofTest");
+    proof
+        .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, n)
+        .map_err(MultiproverError::RangeProofError)
+}
+
+#[tokio::test]
+async fn mpc_range_proof_test() {
+    let (res0, res1) =
+        execute_mock_mpc(|fabric| prove_and_verify(42, 8, Tamper::default(), fabric)).await;
+
+    assert_eq!(res0, Ok(()));
+    assert_eq!(res1, Ok(()));
+}
+
+#[tokio::test]
+async fn mpc_range_proof_out_of_range_test() {
+    // The value is decomposed into its 8 low-order bits, which do not add up to it
+    let (res0, res1) =
+        execute_mock_mpc(|fabric| prove_and_verify(256 + 42, 8, Tamper::default(), fabric)).await;
+
+    let expected = Err(MultiproverError::RangeProofError(
+        ProofError::VerificationError,
+    ));
+    assert_eq!(res0, expected);
+    assert_eq!(res1, expected);
+}
+
+#[tokio::test]
+async fn mpc_range_proof_tampered_share_test() {
+    // A tampered share of the value is caught when the masked value is opened for
+    // bit decomposition, each party attributes the failure to its peer
+    let tamper = Tamper {
+        value: 2,
+        ..Default::default()
+    };
+    let (res0, res1) =
+        execute_mock_mpc(move |fabric| prove_and_verify(42, 8, tamper, fabric)).await;
+
+    assert_eq!(
+        res0,
+        Err(MultiproverError::InconsistentShares {
+            step: ProofStep::BitDecomposition,
+            party: PARTY1,
+        })
+    );
+    assert_eq!(
+        res1,
+        Err(MultiproverError::InconsistentShares {
+            step: ProofStep::BitDecomposition,
+            party: PARTY0,
+        })
+    );
+
+    // A tampered share of the blinding factor first enters a value opened with the
+    // evaluation of t(x), and is caught when the proof is opened
+    let tamper = Tamper {
+        blinding: 2,
+        ..Default::default()
+    };
+    let (res0, res1) =
+        execute_mock_mpc(move |fabric| prove_and_verify(42, 8, tamper, fabric)).await;
+
+    assert_eq!(
+        res0,
+        Err(MultiproverError::InconsistentShares {
+            step: ProofStep::PolynomialEvaluation,
+            party: PARTY1,
+        })
+    );
+    assert_eq!(
+        res1,
+        Err(MultiproverError::InconsistentShares {
+            step: ProofStep::PolynomialEvaluation,
+            party: PARTY0,
+        })
+    );
+}
@@ -213,6 +213,108 @@ fn shuffle_gadget_test_42() {
     kshuffle_helper(42);
 }
 
+// Prove and verify shuffles of each of the given sizes in a single aggregated proof
+fn kshuffle_aggregated_helper(sizes: &[usize]) -> Result<(), R1CSError> {
+    use rand::Rng;
+
+    // Common code
+    let mut rng = thread_rng();
+    let pc_gens = PedersenGens::default();
+    let max_k = sizes.iter().copied().max().unwrap_or(0);
+    let bp_gens = BulletproofGens::new(
+        (2 * max_k).next_power_of_two(),
+        sizes.len().next_power_of_two(),
+    );
+
+    // Randomly generate inputs and outputs to each kshuffle
+    let shuffles: Vec<(Vec<Scalar>, Vec<Scalar>)> = sizes
+        .iter()
+        .map(|k| {
+            let input: Vec<Scalar> = (0..*k)
+                .map(|_| Scalar::from(rng.gen_range(0u64..std::u64::MAX)))
+                .collect();
+            let mut output = input.clone();
+            output.shuffle(&mut thread_rng());
+            (input, output)
+        })
+        .collect();
+
+    // Prover's scope
+    let (proof, commitments) = {
+        let mut transcripts: Vec<Transcript> = sizes
+            .iter()
+            .map(|k| {
+                let mut transcript = Transcript::new(b"ShuffleProofTest");
+                transcript.append_message(b"dom-sep", b"ShuffleProof");
+                transcript.append_u64(b"k", *k as u64);
+                transcript
+            })
+            .collect();
+
+        let mut commitments = Vec::new();
+        let mut provers = Vec::new();
+        for (transcript, (input, output)) in transcripts.iter_mut().zip(shuffles.iter()) {
+            let mut prover = Prover::new(&pc_gens, transcript);
+            let (input_commitments, input_vars): (Vec<_>, Vec<_>) = input
+                .iter()
+                .map(|v| prover.commit(*v, Scalar::random(&mut rng)))
+                .unzip();
+            let (output_commitments, output_vars): (Vec<_>, Vec<_>) = output
+                .iter()
+                .map(|v| prover.commit(*v, Scalar::random(&mut rng)))
+                .unzip();
+
+            ShuffleProof::gadget(&mut prover, input_vars, output_vars)?;
+            commitments.push((input_commitments, output_commitments));
+            provers.push(prover);
+        }
+
+        let mut transcript = Transcript::new(b"AggregatedShuffleProofTest");
+        let proof = Prover::prove_aggregated(provers, &mut transcript, &bp_gens)?;
+        (proof, commitments)
+    };
+
+    // Verifier's scope
+    let mut transcripts: Vec<Transcript> = sizes
+        .iter()
+        .map(|k| {
+            let mut transcript = Transcript::new(b"ShuffleProofTest");
+            transcript.append_message(b"dom-sep", b"ShuffleProof");
+            transcript.append_u64(b"k", *k as u64);
+            transcript
+        })
+        .collect();
+
+    let mut verifiers = Vec::new();
+    for (transcript, (input_commitments, output_commitments)) in
+        transcripts.iter_mut().zip(commitments.iter())
+    {
+        let mut verifier = Verifier::new(&pc_gens, transcript);
+        let input_vars: Vec<_> = input_commitments
+            .iter()
+            .map(|V| verifier.commit(*V))
+            .collect();
+        let output_vars: Vec<_> = output_commitments
+            .iter()
+            .map(|V| verifier.commit(*V))
+            .collect();
+
+        ShuffleProof::gadget(&mut verifier, input_vars, output_vars)?;
+        verifiers.push(verifier);
+    }
+
+    let mut transcript = Transcript::new(b"AggregatedShuffleProofTest");
+    Verifier::verify_aggregated(verifiers, &proof, &mut transcript, &bp_gens)
+}
+
+#[test]
+fn shuffle_gadget_aggregated_test() {
+    assert!(kshuffle_aggregated_helper(&[1]).is_ok());
+    assert!(kshuffle_aggregated_helper(&[2, 4]).is_ok());
+    assert!(kshuffle_aggregated_helper(&[3, 1, 7]).is_ok());
+    assert!(kshuffle_aggregated_helper(&[6, 6, 6, 6, 6]).is_ok());
+}
+
 /// Constrains (a1 + a2) * (b1 + b2) = (c1 + c2)
 fn example_gadget<CS: ConstraintSystem>(
     cs: &mut CS,
@@ -339,6 +441,242 @@ fn example_gadget_roundtrip_serialization_helper(
     example_gadget_verify(&pc_gens, &bp_gens, c2, proof, commitments)
 }
 
+// Verifier logic for a batch of example gadget proofs
+fn example_gadget_batch_verify(
+    pc_gens: &PedersenGens,
+    bp_gens: &BulletproofGens,
+    c2: u64,
+    proofs: &[(R1CSProof, Vec<StarkPoint>)],
+) -> Result<(), R1CSError> {
+    let mut transcripts: Vec<Transcript> = (0..proofs.len())
+        .map(|_| Transcript::new(b"R1CSExampleGadget"))
+        .collect();
+
+    // Build a verifier for each proof
+    let batch: Vec<(Verifier, &R1CSProof)> = transcripts
+        .iter_mut()
+        .zip(proofs.iter())
+        .map(|(transcript, (proof, commitments))| {
+            let mut verifier = Verifier::new(pc_gens, transcript);
+            let vars: Vec<_> = commitments.iter().map(|V| verifier.commit(*V)).collect();
+
+            example_gadget(
+                &mut verifier,
+                vars[0].into(),
+                vars[1].into(),
+                vars[2].into(),
+                vars[3].into(),
+                vars[4].into(),
+                Scalar::from(c2).into(),
+            );
+
+            (verifier, proof)
+        })
+        .collect();
+
+    Verifier::verify_batch(batch, bp_gens)
+}
+
+fn example_gadget_batch_helper(c2_values: &[u64]) -> Result<(), R1CSError> {
+    // Common
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(128, 1);
+
+    let proofs = c2_values
+        .iter()
+        .map(|c2| {
+            example_gadget_proof(
+                &pc_gens,
+                &bp_gens,
+                *EXAMPLE_GADGET_A1,
+                *EXAMPLE_GADGET_A2,
+                *EXAMPLE_GADGET_B1,
+                *EXAMPLE_GADGET_B2,
+                *EXAMPLE_GADGET_C1,
+                *c2,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    example_gadget_batch_verify(&pc_gens, &bp_gens, *EXAMPLE_GADGET_C2, &proofs)
+}
+
+// Prove and verify example gadget instances with each of the given `c2`s in a
+// single aggregated proof, the verifier uses `EXAMPLE_GADGET_C2` throughout
+fn example_gadget_aggregated_helper(c2_values: &[u64]) -> Result<(), R1CSError> {
+    // Common
+    let mut rng = thread_rng();
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(128, c2_values.len().next_power_of_two());
+    let inputs = [
+        *EXAMPLE_GADGET_A1,
+        *EXAMPLE_GADGET_A2,
+        *EXAMPLE_GADGET_B1,
+        *EXAMPLE_GADGET_B2,
+        *EXAMPLE_GADGET_C1,
+    ];
+
+    // Prover's scope
+    let mut transcripts: Vec<Transcript> = (0..c2_values.len())
+        .map(|_| Transcript::new(b"R1CSExampleGadget"))
+        .collect();
+
+    let mut commitments = Vec::new();
+    let mut provers = Vec::new();
+    for (transcript, c2) in transcripts.iter_mut().zip(c2_values.iter()) {
+        let mut prover = Prover::new(&pc_gens, transcript);
+        let (instance_commitments, vars): (Vec<_>, Vec<_>) = inputs
+            .into_iter()
+            .map(|x| prover.commit(Scalar::from(x), Scalar::random(&mut rng)))
+            .unzip();
+
+        example_gadget(
+            &mut prover,
+            vars[0].into(),
+            vars[1].into(),
+            vars[2].into(),
+            vars[3].into(),
+            vars[4].into(),
+            Scalar::from(*c2).into(),
+        );
+        commitments.push(instance_commitments);
+        provers.push(prover);
+    }
+
+    let mut transcript = Transcript::new(b"R1CSAggregatedExampleGadget");
+    let proof = Prover::prove_aggregated(provers, &mut transcript, &bp_gens)?;
+
+    // The aggregated proof is a regular `R1CSProof`, round trip its serialization
+    let proof = R1CSProof::from_bytes(&proof.to_bytes())?;
+
+    // Verifier's scope
+    let mut transcripts: Vec<Transcript> = (0..c2_values.len())
+        .map(|_| Transcript::new(b"R1CSExampleGadget"))
+        .collect();
+
+    let verifiers: Vec<Verifier> = transcripts
+        .iter_mut()
+        .zip(commitments.iter())
+        .map(|(transcript, instance_commitments)| {
+            let mut verifier = Verifier::new(&pc_gens, transcript);
+            let vars: Vec<_> = instance_commitments
+                .iter()
+                .map(|V| verifier.commit(*V))
+                .collect();
+
+            example_gadget(
+                &mut verifier,
+                vars[0].into(),
+                vars[1].into(),
+                vars[2].into(),
+                vars[3].into(),
+                vars[4].into(),
+                Scalar::from(*EXAMPLE_GADGET_C2).into(),
+            );
+
+            verifier
+        })
+        .collect();
+
+    let mut transcript = Transcript::new(b"R1CSAggregatedExampleGadget");
+    Verifier::verify_aggregated(verifiers, &proof, &mut transcript, &bp_gens)
+}
+
+// Build a verification key for the example gadget from the verifier's weights
+fn example_gadget_verification_key(pc_gens: &PedersenGens, c2: u64) -> VerificationKey {
+    let mut transcript = Transcript::new(b"R1CSExampleGadget");
+    let mut verifier = Verifier::new(pc_gens, &mut transcript);
+
+    // The commitments are placeholders, only the structure of the circuit is kept
+    let vars: Vec<_> = (0..5)
+        .map(|_| verifier.commit(StarkPoint::identity()))
+        .collect();
+
+    example_gadget(
+        &mut verifier,
+        vars[0].into(),
+        vars[1].into(),
+        vars[2].into(),
+        vars[3].into(),
+        vars[4].into(),
+        Scalar::from(c2).into(),
+    );
+
+    let phases = PhaseSizes {
+        n1: verifier.num_multipliers(),
+        n2: 0,
+        m: vars.len(),
+    };
+    VerificationKey::new(verifier.get_weights(), phases).unwrap()
+}
+
+fn example_gadget_verification_key_helper(c2: u64) -> Result<(), R1CSError> {
+    // Common
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(128, 1);
+
+    let (proof, commitments) = example_gadget_proof(
+        &pc_gens,
+        &bp_gens,
+        *EXAMPLE_GADGET_A1,
+        *EXAMPLE_GADGET_A2,
+        *EXAMPLE_GADGET_B1,
+        *EXAMPLE_GADGET_B2,
+        *EXAMPLE_GADGET_C1,
+        c2,
+    )?;
+
+    // Round trip the key through its serialization, as if shipped to a verifier
+    let key = example_gadget_verification_key(&pc_gens, *EXAMPLE_GADGET_C2);
+    let key: VerificationKey = bincode::deserialize(&bincode::serialize(&key).unwrap()).unwrap();
+
+    let mut transcript = Transcript::new(b"R1CSExampleGadget");
+    key.verify(&proof, &commitments, &pc_gens, &bp_gens, &mut transcript)
+}
+
+fn example_gadget_witness_helper(c2: u64) -> Result<(), R1CSError> {
+    // Common
+    let mut rng = thread_rng();
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(128, 1);
+    let key = example_gadget_verification_key(&pc_gens, c2);
+
+    // The gadget allocates a single multiplier, (a1 + a2) * (b1 + b2)
+    let (a1, a2, b1, b2, c1) = (
+        *EXAMPLE_GADGET_A1,
+        *EXAMPLE_GADGET_A2,
+        *EXAMPLE_GADGET_B1,
+        *EXAMPLE_GADGET_B2,
+        *EXAMPLE_GADGET_C1,
+    );
+    let witness = Witness {
+        a_L: vec![Scalar::from(a1 + a2)],
+        a_R: vec![Scalar::from(b1 + b2)],
+        a_O: vec![Scalar::from((a1 + a2) * (b1 + b2))],
+        v: [a1, a2, b1, b2, c1].into_iter().map(Scalar::from).collect(),
+        v_blinding: (0..5).map(|_| Scalar::random(&mut rng)).collect(),
+    };
+
+    let mut prover_transcript = Transcript::new(b"R1CSExampleGadget");
+    let (proof, commitments) = Prover::prove_with_weights(
+        &pc_gens,
+        &mut prover_transcript,
+        key.weights(),
+        key.phases(),
+        &witness,
+        &bp_gens,
+    )?;
+
+    let mut verifier_transcript = Transcript::new(b"R1CSExampleGadget");
+    key.verify(
+        &proof,
+        &commitments,
+        &pc_gens,
+        &bp_gens,
+        &mut verifier_transcript,
+    )
+}
+
 // Extract weight matrix for example gadget from prover
 fn example_gadget_constraint_ir_prover(
     a1: u64,
@@ -586,6 +924,52 @@ fn example_gadget_serialization_test() {
     .is_err());
 }
 
+#[test]
+fn example_gadget_batch_test() {
+    // (3 + 4) * (6 + 1) = (40 + 9)
+    let c2 = *EXAMPLE_GADGET_C2;
+    assert!(example_gadget_batch_helper(&[c2, c2, c2]).is_ok());
+    // (3 + 4) * (6 + 1) != (40 + 10)
+    assert_eq!(
+        example_gadget_batch_helper(&[c2, 10, c2, 10]),
+        Err(R1CSError::BatchVerificationError {
+            bad_proofs: vec![1, 3]
+        })
+    );
+    // An empty batch is rejected
+    assert_eq!(
+        example_gadget_batch_helper(&[]),
+        Err(R1CSError::InvalidAggregation)
+    );
+}
+
+#[test]
+fn example_gadget_aggregated_test() {
+    // (3 + 4) * (6 + 1) = (40 + 9)
+    let c2 = *EXAMPLE_GADGET_C2;
+    assert!(example_gadget_aggregated_helper(&[c2]).is_ok());
+    assert!(example_gadget_aggregated_helper(&[c2, c2, c2]).is_ok());
+    // (3 + 4) * (6 + 1) != (40 + 10)
+    assert!(example_gadget_aggregated_helper(&[c2, 10, c2]).is_err());
+    assert!(example_gadget_aggregated_helper(&[]).is_err());
+}
+
+#[test]
+fn example_gadget_verification_key_test() {
+    // (3 + 4) * (6 + 1) = (40 + 9)
+    assert!(example_gadget_verification_key_helper(*EXAMPLE_GADGET_C2).is_ok());
+    // (3 + 4) * (6 + 1) != (40 + 10)
+    assert!(example_gadget_verification_key_helper(10).is_err());
+}
+
+#[test]
+fn example_gadget_witness_test() {
+    // (3 + 4) * (6 + 1) = (40 + 9)
+    assert!(example_gadget_witness_helper(*EXAMPLE_GADGET_C2).is_ok());
+    // (3 + 4) * (6 + 1) != (40 + 10)
+    assert!(example_gadget_witness_helper(10).is_err());
+}
+
 #[test]
 fn example_gadget_constraint_ir_prover_test() {
     let circuit_weights = example_gadget_constraint_ir_prover(
@@ -701,3 +1085,542 @@ fn range_proof_helper(v_val: u64, n: usize) -> Result<(), R1CSError> {
     // Verifier verifies proof
     verifier.verify(&proof, &bp_gens)
 }
+
+#[test]
+fn unsatisfied_constraints_test() {
+    let pc_gens = PedersenGens::default();
+    let mut transcript = Transcript::new(b"UnsatisfiedConstraintsTest");
+    let mut prover = Prover::new(&pc_gens, &mut transcript);
+
+    // 5 is not in the range [0, 4), so the final constraint of the gadget fails
+    let (_, var) = prover.commit(Scalar::from(5u64), Scalar::random(&mut thread_rng()));
+    prover.push_namespace("range");
+    assert!(range_proof(&mut prover, var.into(), Some(5), 2).is_ok());
+    prover.pop_namespace();
+    prover.constrain_with_label(var - 6u64, "equals six");
+
+    assert!(!prover.constraints_satisfied());
+    assert_eq!(
+        prover.unsatisfied_constraints(),
+        vec![
+            UnsatisfiedConstraint {
+                index: 4,
+                // Namespaces are only recorded with the `namespaces` feature
+                label: cfg!(feature = "namespaces").then(|| "range".to_string()),
+            },
+            UnsatisfiedConstraint {
+                index: 5,
+                label: Some("equals six".to_string()),
+            },
+        ]
+    );
+}
+
+/// Multiplies two committed values within nested namespaces
+#[cfg(feature = "namespaces")]
+fn namespaced_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    a: Variable,
+    b: Variable,
+) -> Result<(), R1CSError> {
+    let mut cs = cs.namespace("outer");
+    let (_, _, o) = cs.multiply(a.into(), b.into());
+
+    let mut cs = cs.namespace("inner");
+    let (_, _, o2) = cs.multiply(o.into(), Variable::One().into());
+    cs.constrain_with_label(o2 - 6u64, "equals six");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "namespaces")]
+fn namespaces_test() {
+    let pc_gens = PedersenGens::default();
+    let mut rng = thread_rng();
+
+    let mut prover_transcript = Transcript::new(b"NamespacesTest");
+    let mut prover = Prover::new(&pc_gens, &mut prover_transcript);
+    let (commitments, vars): (Vec<_>, Vec<_>) = [2u64, 3u64]
+        .into_iter()
+        .map(|v| prover.commit(Scalar::from(v), Scalar::random(&mut rng)))
+        .unzip();
+    namespaced_gadget(&mut prover, vars[0], vars[1]).unwrap();
+
+    let namespaces = prover.get_namespaces();
+    assert_eq!(namespaces.multipliers.get(&0).unwrap(), "outer");
+    assert_eq!(namespaces.multipliers.get(&1).unwrap(), "outer/inner");
+    assert_eq!(
+        namespaces.constraints.values().last().unwrap(),
+        "outer/inner/equals six"
+    );
+    assert_eq!(
+        namespaces.variable_name(&Variable::MultiplierOutput(1)),
+        "outer/inner/out[1]"
+    );
+    assert_eq!(namespaces.variable_name(&vars[0]), "v[0]");
+
+    // The verifier records the same namespaces
+    let mut verifier_transcript = Transcript::new(b"NamespacesTest");
+    let mut verifier = Verifier::new(&pc_gens, &mut verifier_transcript);
+    let vars: Vec<_> = commitments
+        .into_iter()
+        .map(|c| verifier.commit(c))
+        .collect();
+    namespaced_gadget(&mut verifier, vars[0], vars[1]).unwrap();
+
+    assert_eq!(verifier.get_namespaces(), namespaces);
+}
+
+#[test]
+#[cfg(feature = "namespaces")]
+fn namespace_error_test() {
+    let pc_gens = PedersenGens::default();
+    let mut transcript = Transcript::new(b"NamespaceErrorTest");
+    let mut prover = Prover::new(&pc_gens, &mut transcript);
+
+    let res = prover.namespace("merkle/level3").allocate(None);
+    match res {
+        Err(R1CSError::InNamespace { namespace, error }) => {
+            assert_eq!(namespace, "merkle/level3");
+            assert_eq!(*error, R1CSError::MissingAssignment);
+        }
+        _ => panic!("expected an error attributed to the namespace"),
+    }
+}
+
+/// Apply the shuffle gadget to committed values in a test constraint system, with a
+/// fixed challenge
+fn shuffle_test_cs(x: &[u64], y: &[u64], z: u64) -> TestConstraintSystem {
+    let mut cs = TestConstraintSystem::new();
+    cs.set_challenge(b"shuffle challenge", Scalar::from(z));
+
+    let x = x.iter().map(|v| cs.commit(Scalar::from(*v))).collect();
+    let y = y.iter().map(|v| cs.commit(Scalar::from(*v))).collect();
+    ShuffleProof::gadget(&mut cs, x, y).unwrap();
+    cs.finalize().unwrap();
+
+    cs
+}
+
+#[test]
+fn test_constraint_system_shuffle_test() {
+    let cs = shuffle_test_cs(&[1, 2, 3], &[3, 1, 2], 10);
+    assert!(cs.is_satisfied());
+
+    // The shuffle gadget allocates everything in the randomized phase
+    assert_eq!(cs.phase_one_counts(), PhaseCounts::default());
+    assert_eq!(
+        cs.phase_two_counts(),
+        PhaseCounts {
+            multipliers: 4,
+            constraints: 9,
+        }
+    );
+    assert_eq!(cs.phase_sizes(), PhaseSizes { n1: 0, n2: 4, m: 6 });
+
+    // Not a permutation
+    let cs = shuffle_test_cs(&[1, 2, 3], &[1, 5, 6], 10);
+    assert!(!cs.is_satisfied());
+    assert_eq!(cs.unsatisfied_constraints()[0].index, 8);
+
+    // Not a permutation, but the challenge is a root of both products
+    let cs = shuffle_test_cs(&[1, 2, 3], &[1, 5, 6], 1);
+    assert!(cs.is_satisfied());
+}
+
+#[test]
+fn test_constraint_system_pending_multiplier_test() {
+    let mut cs = TestConstraintSystem::new();
+
+    // The constraint on the output is evaluated once the multiplier is completed
+    let l = cs.allocate(Some(Scalar::from(2u64))).unwrap();
+    cs.constrain(Variable::MultiplierOutput(0) - 6u64);
+    assert!(cs.is_satisfied());
+
+    let r = cs.allocate(Some(Scalar::from(3u64))).unwrap();
+    assert!(cs.is_satisfied());
+    assert_eq!(
+        (l, r),
+        (Variable::MultiplierLeft(0), Variable::MultiplierRight(0))
+    );
+
+    // A pending multiplier's right is assigned to zero at the end of the phase
+    cs.allocate(Some(Scalar::from(2u64))).unwrap();
+    cs.constrain(Variable::MultiplierOutput(1) - 4u64);
+    cs.finalize().unwrap();
+    assert_eq!(cs.unsatisfied_constraints()[0].index, 1);
+    assert_eq!(
+        cs.phase_one_counts(),
+        PhaseCounts {
+            multipliers: 2,
+            constraints: 2,
+        }
+    );
+}
+
+/// A circuit with gadgets in separate namespaces, one of which is randomized
+fn profiled_circuit<CS: RandomizableConstraintSystem>(
+    cs: &mut CS,
+    x: Vec<Variable>,
+    y: Vec<Variable>,
+    v: Variable,
+    v_assignment: Option<u64>,
+) -> Result<(), R1CSError> {
+    ShuffleProof::gadget(&mut *cs.namespace("shuffle"), x, y)?;
+    range_proof(
+        &mut *cs.namespace("checks/range"),
+        v.into(),
+        v_assignment,
+        2,
+    )?;
+    cs.allocate(v_assignment.map(Scalar::from))?;
+
+    Ok(())
+}
+
+#[test]
+fn circuit_profile_test() {
+    let pc_gens = PedersenGens::default();
+    let mut rng = thread_rng();
+
+    let mut prover_transcript = Transcript::new(b"CircuitProfileTest");
+    let mut prover = Prover::new(&pc_gens, &mut prover_transcript);
+    let (commitments, vars): (Vec<_>, Vec<_>) = [1u64, 2, 3, 3, 1, 2, 3]
+        .into_iter()
+        .map(|v| prover.commit(Scalar::from(v), Scalar::random(&mut rng)))
+        .unzip();
+    profiled_circuit(
+        &mut prover,
+        vars[..3].to_vec(),
+        vars[3..6].to_vec(),
+        vars[6],
+        Some(3),
+    )
+    .unwrap();
+    let profile = prover.finalize().unwrap().profile;
+
+    assert_eq!(
+        profile.phase_one,
+        GadgetCost {
+            multipliers: 3,
+            constraints: 5,
+            allocations: 1,
+        }
+    );
+    assert_eq!(
+        profile.phase_two,
+        GadgetCost {
+            multipliers: 4,
+            constraints: 9,
+            allocations: 0,
+        }
+    );
+    assert_eq!(profile.padded_n(), 8);
+    assert!(profile.to_string().starts_with("padded_n = 8\n"));
+
+    // Without namespaces, the whole cost is attributed to the root scope
+    #[cfg(feature = "namespaces")]
+    check_profile_scopes(&profile);
+    #[cfg(not(feature = "namespaces"))]
+    assert!(profile.children.is_empty());
+
+    // The verifier attributes the same costs
+    let mut verifier_transcript = Transcript::new(b"CircuitProfileTest");
+    let mut verifier = Verifier::new(&pc_gens, &mut verifier_transcript);
+    let vars: Vec<_> = commitments
+        .into_iter()
+        .map(|c| verifier.commit(c))
+        .collect();
+    profiled_circuit(
+        &mut verifier,
+        vars[..3].to_vec(),
+        vars[3..6].to_vec(),
+        vars[6],
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(verifier.finalize().unwrap().profile, profile);
+}
+
+/// Check the costs attributed to the namespaces of `profiled_circuit`
+#[cfg(feature = "namespaces")]
+fn check_profile_scopes(profile: &CircuitProfile) {
+    // The randomized constraints are attributed to the namespace they were specified in
+    let shuffle = profile.scope("shuffle").unwrap();
+    assert_eq!(shuffle.phase_one, GadgetCost::default());
+    assert_eq!(profile.phase_two, shuffle.phase_two);
+
+    let range = profile.scope("checks/range").unwrap();
+    assert_eq!(
+        range.total(),
+        GadgetCost {
+            multipliers: 2,
+            constraints: 5,
+            allocations: 0,
+        }
+    );
+    assert_eq!(profile.scope("checks").unwrap().total(), range.total());
+
+    assert!(profile.to_string().contains(
+        "\n    range: 2 multipliers (0 randomized), 5 constraints (0 randomized), 0 allocations\n"
+    ));
+    assert!(profile.to_json().contains("\"name\": \"shuffle\""));
+}
+
+/// Compute the fingerprint of the range proof gadget over `n` bits
+fn range_proof_fingerprint(pc_gens: &PedersenGens, n: usize) -> CircuitFingerprint {
+    let mut transcript = Transcript::new(b"FingerprintTest");
+    let mut verifier = Verifier::new(pc_gens, &mut transcript);
+
+    // The fingerprint does not depend on the value committed to
+    let var = verifier.commit(pc_gens.commit(Scalar::zero(), Scalar::one()));
+    range_proof(&mut verifier, var.into(), None, n).unwrap();
+
+    verifier.finalize().unwrap().fingerprint()
+}
+
+/// Prove a range proof over `prover_n` bits bound to `prover_fingerprint`, returning the
+/// proof, after a serialization round trip, and the commitment to the value
+fn fingerprint_prove(
+    pc_gens: &PedersenGens,
+    bp_gens: &BulletproofGens,
+    prover_fingerprint: Option<CircuitFingerprint>,
+    prover_n: usize,
+) -> Result<(R1CSProof, StarkPoint), R1CSError> {
+    let v_val = 10u64;
+
+    let mut prover_transcript = Transcript::new(b"FingerprintTest");
+    let mut prover = match prover_fingerprint {
+        Some(fingerprint) => {
+            Prover::new_with_fingerprint(pc_gens, &mut prover_transcript, fingerprint)
+        }
+        None => Prover::new(pc_gens, &mut prover_transcript),
+    };
+    let (commitment, var) = prover.commit(v_val.into(), Scalar::random(&mut thread_rng()));
+    range_proof(&mut prover, var.into(), Some(v_val), prover_n)?;
+    let proof = prover.prove(bp_gens)?;
+
+    let bytes = proof.to_bytes();
+    assert_eq!(bytes.len(), proof.serialized_size());
+    let proof = R1CSProof::from_bytes(&bytes)?;
+    assert_eq!(proof.fingerprint, prover_fingerprint);
+
+    Ok((proof, commitment))
+}
+
+/// Verify a proof with a verifier of an 8 bit range proof bound to
+/// `verifier_fingerprint`
+fn fingerprint_verify(
+    pc_gens: &PedersenGens,
+    bp_gens: &BulletproofGens,
+    proof: &R1CSProof,
+    commitment: StarkPoint,
+    verifier_fingerprint: Option<CircuitFingerprint>,
+) -> Result<(), R1CSError> {
+    let mut verifier_transcript = Transcript::new(b"FingerprintTest");
+    let mut verifier = match verifier_fingerprint {
+        Some(fingerprint) => {
+            Verifier::new_with_fingerprint(pc_gens, &mut verifier_transcript, fingerprint)
+        }
+        None => Verifier::new(pc_gens, &mut verifier_transcript),
+    };
+    let var = verifier.commit(commitment);
+    range_proof(&mut verifier, var.into(), None, 8)?;
+    verifier.verify(proof, bp_gens)
+}
+
+/// Prove a range proof over `prover_n` bits bound to `prover_fingerprint`, and verify
+/// it with a verifier of an 8 bit range proof bound to `verifier_fingerprint`; either
+/// may be unbound
+fn fingerprint_helper(
+    prover_fingerprint: Option<CircuitFingerprint>,
+    prover_n: usize,
+    verifier_fingerprint: Option<CircuitFingerprint>,
+) -> Result<(), R1CSError> {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(128, 1);
+
+    let (proof, commitment) = fingerprint_prove(&pc_gens, &bp_gens, prover_fingerprint, prover_n)?;
+    fingerprint_verify(&pc_gens, &bp_gens, &proof, commitment, verifier_fingerprint)
+}
+
+#[test]
+fn circuit_fingerprint_test() {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(128, 1);
+    let fingerprint = range_proof_fingerprint(&pc_gens, 8);
+    let other_fingerprint = range_proof_fingerprint(&pc_gens, 16);
+    assert_eq!(fingerprint, range_proof_fingerprint(&pc_gens, 8));
+    assert_ne!(fingerprint, other_fingerprint);
+
+    assert!(fingerprint_helper(None, 8, None).is_ok());
+    assert!(fingerprint_helper(Some(fingerprint), 8, Some(fingerprint)).is_ok());
+
+    // A proof bound to a circuit only verifies against a verifier bound to it, and
+    // the fingerprint carried by the proof rejects it before its transcript is replayed
+    assert_eq!(
+        fingerprint_helper(Some(fingerprint), 8, None),
+        Err(R1CSError::CircuitFingerprintMismatch)
+    );
+    assert_eq!(
+        fingerprint_helper(None, 8, Some(fingerprint)),
+        Err(R1CSError::CircuitFingerprintMismatch)
+    );
+
+    // A circuit that does not match its fingerprint fails with a dedicated error, on
+    // either side
+    assert_eq!(
+        fingerprint_helper(Some(other_fingerprint), 8, Some(other_fingerprint)),
+        Err(R1CSError::CircuitFingerprintMismatch)
+    );
+    assert_eq!(
+        fingerprint_helper(Some(fingerprint), 8, Some(other_fingerprint)),
+        Err(R1CSError::CircuitFingerprintMismatch)
+    );
+
+    // A proof of a different circuit layout, bound to its own fingerprint, is rejected
+    // by the fingerprint it carries
+    assert_eq!(
+        fingerprint_helper(Some(other_fingerprint), 16, Some(fingerprint)),
+        Err(R1CSError::CircuitFingerprintMismatch)
+    );
+
+    // Replacing the fingerprint carried by the proof does not rebind its transcript
+    let (mut proof, commitment) =
+        fingerprint_prove(&pc_gens, &bp_gens, Some(other_fingerprint), 16).unwrap();
+    proof.fingerprint = Some(fingerprint);
+    assert_eq!(
+        fingerprint_verify(&pc_gens, &bp_gens, &proof, commitment, Some(fingerprint)),
+        Err(R1CSError::VerificationError)
+    );
+}
+
+#[test]
+fn circuit_weights_format_test() {
+    let weights = example_gadget_constraint_ir_prover(
+        *EXAMPLE_GADGET_A1,
+        *EXAMPLE_GADGET_A2,
+        *EXAMPLE_GADGET_B1,
+        *EXAMPLE_GADGET_B2,
+        *EXAMPLE_GADGET_C1,
+        *EXAMPLE_GADGET_C2,
+    );
+    let dimensions = weights.dimensions();
+    assert_eq!(dimensions, CircuitDimensions { n: 1, m: 5, q: 3 });
+
+    // Binary format
+    let bytes = weights.to_bytes(&dimensions).unwrap();
+    assert_eq!(bytes[..4], CIRCUIT_FORMAT_MAGIC);
+    assert_eq!(bytes[4], CIRCUIT_FORMAT_VERSION);
+    assert_eq!(
+        CircuitWeights::from_bytes(&bytes).unwrap(),
+        (weights, dimensions)
+    );
+
+    assert_eq!(
+        CircuitWeights::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(R1CSError::FormatError)
+    );
+    assert_eq!(
+        CircuitWeights::from_bytes(&[bytes.as_slice(), &[0]].concat()),
+        Err(R1CSError::FormatError)
+    );
+
+    let mut unknown_version = bytes.clone();
+    unknown_version[4] = CIRCUIT_FORMAT_VERSION + 1;
+    assert_eq!(
+        CircuitWeights::from_bytes(&unknown_version),
+        Err(R1CSError::FormatError)
+    );
+
+    // Declare fewer multipliers than the weights reference
+    let mut too_few_multipliers = bytes.clone();
+    too_few_multipliers[5..9].copy_from_slice(&0u32.to_le_bytes());
+    assert!(matches!(
+        CircuitWeights::from_bytes(&too_few_multipliers),
+        Err(R1CSError::GadgetError { .. })
+    ));
+
+    // Declare more constraints than the input can hold
+    let mut too_many_constraints = bytes.clone();
+    too_many_constraints[13..17].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        CircuitWeights::from_bytes(&too_many_constraints),
+        Err(R1CSError::FormatError)
+    );
+
+    // JSON format
+    let (weights, _) = CircuitWeights::from_bytes(&bytes).unwrap();
+    let json = weights.to_json(&dimensions).unwrap();
+    assert_eq!(
+        CircuitWeights::from_json(&json).unwrap(),
+        (weights, dimensions)
+    );
+
+    let (weights, _) = CircuitWeights::from_bytes(&bytes).unwrap();
+    assert!(weights
+        .to_json(&CircuitDimensions { n: 1, m: 4, q: 3 })
+        .is_err());
+}
+
+#[test]
+#[cfg(feature = "namespaces")]
+fn circuit_dot_test() {
+    let pc_gens = PedersenGens::default();
+    let mut transcript = Transcript::new(b"CircuitDotTest");
+    let mut verifier = Verifier::new(&pc_gens, &mut transcript);
+    let vars: Vec<_> = (0..7)
+        .map(|_| verifier.commit(pc_gens.commit(Scalar::zero(), Scalar::zero())))
+        .collect();
+    profiled_circuit(
+        &mut verifier,
+        vars[..3].to_vec(),
+        vars[3..6].to_vec(),
+        vars[6],
+        None,
+    )
+    .unwrap();
+    let dot = verifier.finalize().unwrap().to_dot().unwrap();
+
+    assert!(dot.starts_with("digraph circuit {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("    v6 [shape=ellipse, label=\"v[6]\"];\n"));
+
+    // The range proof and the allocation are in the first phase, the shuffle is randomized
+    let phase_one = dot.find("subgraph cluster_phase_one {").unwrap();
+    let phase_two = dot.find("subgraph cluster_phase_two {").unwrap();
+    let range = dot
+        .find("    m0 [shape=record, label=\"{<l> left|<r> right}|checks/range/mul[0]|<o> out\"];")
+        .unwrap();
+    let allocation = dot.find("|mul[2]|").unwrap();
+    let shuffle = dot.find("|shuffle/mul[3]|").unwrap();
+    assert!(phase_one < range && range < allocation && allocation < phase_two);
+    assert!(phase_two < shuffle);
+    assert!(!dot.contains("m7 ["));
+
+    // Constraints join committed variables and multiplier ports
+    assert!(dot.contains("  v6 -> c"));
+    assert!(dot.contains(":o -> c"));
+    assert!(dot.contains(" -> m3:l;\n"));
+
+    // The weights must fit within the phase sizes
+    let weights = example_gadget_constraint_ir_prover(
+        *EXAMPLE_GADGET_A1,
+        *EXAMPLE_GADGET_A2,
+        *EXAMPLE_GADGET_B1,
+        *EXAMPLE_GADGET_B2,
+        *EXAMPLE_GADGET_C1,
+        *EXAMPLE_GADGET_C2,
+    );
+    let namespaces = CircuitNamespaces::default();
+    let dot = weights
+        .to_dot(&PhaseSizes { n1: 1, n2: 0, m: 5 }, &namespaces)
+        .unwrap();
+    assert!(!dot.contains("cluster_phase_two"));
+    assert!(matches!(
+        weights.to_dot(&PhaseSizes { n1: 1, n2: 0, m: 4 }, &namespaces),
+        Err(R1CSError::GadgetError { .. })
+    ));
+}